@@ -1,103 +1,619 @@
 #include "AudioInterface.h"
 
-extern "C" AudioContext init(void (*end_callback)(void*)) {
+static AudioContext initWithBackends(void (*end_callback)(void*), const ma_backend* backends, ma_uint32 backendCount, bool offline, const EngineConfig* config) {
 	std::lock_guard<std::mutex> lock(global);
 	ma_context* context = new ma_context();
-	if(ma_context_init(NULL, 0, NULL, context) != MA_SUCCESS){
+	if(ma_context_init(backends, backendCount, NULL, context) != MA_SUCCESS){
 		std::cout << "Failed to initialize context" << std::endl;
 		delete context;
-		return AudioContext{nullptr, nullptr, false, nullptr};
+		return AudioContext{nullptr, nullptr, false, false, nullptr, nullptr, nullptr, nullptr};
 	}
 
 	endCallback = end_callback;
 
-	return AudioContext{context, new std::unordered_map<size_t, SoundClip*>, true, new std::mutex()};
+	ContextState* state = new ContextState();
+	state->paused = false;
+	state->masterVolume = 1;
+	state->clock = 0;
+	state->memoryBudget = 0;
+	for(int axis = 0; axis < 3; axis++) {
+		state->listener.position[axis] = 0;
+		state->listener.direction[axis] = 0;
+	}
+	state->listener.direction[2] = -1;
+	state->config = config == nullptr ? EngineConfig{0, 0, 0, -1, 0} : *config;
+
+	//exclusive mode is checked on the default device once so the context can report what it got
+	if(state->config.shareMode == 1){
+		ma_device_config probeConfig = ma_device_config_init(ma_device_type_playback);
+		probeConfig.periodSizeInFrames = state->config.bufferFrames;
+		probeConfig.playback.shareMode = ma_share_mode_exclusive;
+		std::unique_ptr<ma_device> probe{new ma_device};
+		if(ma_device_init(context, &probeConfig, probe.get()) == MA_SUCCESS){
+			ma_device_uninit(probe.get());
+		} else {
+			std::cout << "Exclusive mode is not available, falling back to shared mode" << std::endl;
+			state->config.shareMode = 0;
+		}
+	}
+
+	return AudioContext{context, new std::unordered_map<size_t, SoundClip*>, true, offline, new std::mutex(), nullptr, nullptr, state};
+}
+
+extern "C" AudioContext init(void (*end_callback)(void*)) {
+	return initWithBackends(end_callback, NULL, 0, false, nullptr);
+}
+
+//the preferred backend is tried first and the others follow in the default order as fallbacks
+extern "C" AudioContext initConfig(void (*end_callback)(void*), const EngineConfig* config) {
+	if(config->backend < 0 || config->backend >= MA_BACKEND_COUNT){
+		return initWithBackends(end_callback, NULL, 0, false, config);
+	}
+	std::vector<ma_backend> backends{(ma_backend)config->backend};
+	for(int backend = 0; backend < MA_BACKEND_COUNT; backend++){
+		if(backend != config->backend && backend != ma_backend_custom){
+			backends.push_back((ma_backend)backend);
+		}
+	}
+	return initWithBackends(end_callback, backends.data(), (ma_uint32)backends.size(), false, config);
+}
+
+extern "C" int getBackend(AudioContext* context) {
+	return context->context->backend;
+}
+
+extern "C" const char* getVersion() {
+	return ma_version_string();
+}
+
+//backends compiled into this build, the custom backend is left out since it is never used
+extern "C" size_t getEnabledBackends(int32_t* backends, size_t capacity) {
+	ma_backend enabled[MA_BACKEND_COUNT];
+	size_t count = 0;
+	if(ma_get_enabled_backends(enabled, MA_BACKEND_COUNT, &count) != MA_SUCCESS){
+		return 0;
+	}
+	size_t written = 0;
+	for(size_t i = 0; i < count && written < capacity; i++){
+		if(enabled[i] != ma_backend_custom){
+			backends[written++] = enabled[i];
+		}
+	}
+	return written;
+}
+
+extern "C" size_t getSoundCount(AudioContext* context) {
+	std::lock_guard<std::mutex> lock(*context->mtx);
+	return context->soundClips->size();
+}
+
+extern "C" AudioContext initNull(void (*end_callback)(void*)) {
+	ma_backend backends[] = {ma_backend_null};
+	return initWithBackends(end_callback, backends, 1, false, nullptr);
+}
+
+extern "C" AudioContext initOffline(void (*end_callback)(void*)) {
+	ma_backend backends[] = {ma_backend_null};
+	return initWithBackends(end_callback, backends, 1, true, nullptr);
+}
+
+//threads that still hold the clip skip their work from here on and the last of them deletes it
+static void releaseClip(SoundClip* clip) {
+	{
+		std::unique_lock<std::mutex> lock(clip->mtx);
+		clip->removed = true;
+		//a callback that drops the last handle of its own clip can not wait for itself
+		int own = runningCallback == clip ? 1 : 0;
+		clip->callbacksDone.wait(lock, [clip, own](){ return clip->callbacks == own; });
+		clip->stopping = true;
+		ma_device_uninit(&clip->device);
+		ma_decoder_uninit(&clip->decoder);
+		endBlockTap(clip);
+	}
+	releaseClipRef(clip);
 }
 
 extern "C" void uninit(AudioContext* context) {
 	std::lock_guard<std::mutex> lock(global);
+	for(auto& pair : *context->soundClips) {
+		releaseClip(pair.second);
+	}
+	for(Recorder* recorder : context->state->recorders) {
+		ma_device_uninit(&recorder->device);
+		delete recorder;
+	}
 	delete context->soundClips;
 	ma_context_uninit(context->context);
+	delete context->context;
 	delete context->mtx;
+	delete context->state;
 }
 
 extern "C" void setVolume(size_t id, AudioContext* context, float value) {
+	cancelFade(context->soundClips->at(id));
 	context->soundClips->at(id)->device.masterVolumeFactor = value;
 }
 
 extern "C" float getVolume(size_t id, AudioContext* context) {
-	return context->soundClips->at(id)->device.masterVolumeFactor;
+	return clipVolume(context->soundClips->at(id));
 }
 
-extern "C" void play(size_t id, AudioContext* context){
-	if(!ma_device_is_started(&context->soundClips->at(id)->device)){
-		if(ma_device_start(&context->soundClips->at(id)->device) != MA_SUCCESS) {
+//a negative start fades from the current volume and stopping restores that volume once paused
+extern "C" void fade(size_t id, AudioContext* context, float from, float to, uint64_t micros, bool stop, int curve) {
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	float current = getVolume(id, context);
+	cancelFade(clip);
+	if(from < 0){
+		from = current;
+	}
+	ma_uint64 frames = micros * clip->device.sampleRate / 1000000;
+	if(frames == 0){
+		clip->device.masterVolumeFactor = stop ? current : to;
+		if(stop){
+			stopClip(clip);
+		}
+		return;
+	}
+	clip->fadeVolume = from;
+	clip->fadeFrom = from;
+	clip->fadeTarget = to;
+	clip->fadeElapsed = 0;
+	clip->fadeFrames = frames;
+	clip->fadeCurve = curve;
+	clip->fadeRestore = current;
+	clip->fadeStop = stop;
+	clip->fading = true;
+	clip->device.masterVolumeFactor = 1;
+}
+
+//playback controls return 0 on success, -2 if the device failed and -4 if seeking failed
+extern "C" int play(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	//held so a seek can not race the device starting
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	//an ended clip is already rewound so replaying only needs the flag cleared
+	if(clip->ended){
+		if(!clip->replayOnPlay){
+			return 0;
+		}
+		clip->ended = false;
+	}
+	//a paused engine starts the clip once it is resumed
+	if(context->state->paused){
+		clip->resumeWithEngine = true;
+		return 0;
+	}
+	if(!isClipStarted(clip)){
+		if(startClip(clip) != MA_SUCCESS) {
 			std::cout << "Failed to start playback" << std::endl;
+			return -2;
 		}
 	}
+	return 0;
 }
 
-extern "C" void reset(size_t id, AudioContext* context) {
-	std::lock_guard<std::mutex> lock(context->soundClips->at(id)->mtx);
-	ma_device_stop(&context->soundClips->at(id)->device);
-	ma_decoder_seek_to_pcm_frame(&context->soundClips->at(id)->decoder, 0);
+extern "C" int reset(size_t id, AudioContext* context) {
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	if(clip->fadeStop){
+		cancelFade(clip);
+	}
+	if(isClipStarted(clip) && stopClip(clip) != MA_SUCCESS){
+		return -2;
+	}
+	clip->ended = false;
+	clip->silentFrames = 0;
+	clip->stretchReset = true;
+	if(!seekClip(clip, clip->rangeStart)){
+		return -4;
+	}
+	return 0;
 }
 
-extern "C" void stop(size_t id, AudioContext* context) {
-	ma_device_stop(&context->soundClips->at(id)->device);
+//a running device is kept running and only rewound so there is no gap between the two plays
+extern "C" int replay(size_t id, AudioContext* context) {
+	SoundClip* clip = context->soundClips->at(id);
+	{
+		std::lock_guard<std::mutex> lock(clip->mtx);
+		clip->ended = false;
+		clip->silentFrames = 0;
+		clip->stretchReset = true;
+		if(!seekClip(clip, clip->rangeStart)){
+			return -4;
+		}
+	}
+	return play(id, context);
+}
+
+//positions are relative to the start of the range and past the end are clamped to the end
+extern "C" bool seek(size_t id, AudioContext* context, uint64_t micros) {
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	ma_uint64 length = clipEnd(clip);
+	ma_uint64 frame = clip->rangeStart + micros * clip->decoder.outputSampleRate / 1000000;
+	if((length != 0 || clip->lengthKnown) && frame > length){
+		frame = length;
+	}
+	if(!seekClip(clip, frame)){
+		return false;
+	}
+	clip->ended = false;
+	clip->silentFrames = 0;
+	clip->stretchReset = true;
+	return true;
 }
 
-extern "C" int load(size_t id, AudioContext* context, const char* path, AudioDevice* device) {
+//every built in decoder can seek as long as its source can
+extern "C" bool isSeekable(size_t id, AudioContext* context) {
+	SoundClip* clip = context->soundClips->at(id);
+	if(clip->decoder.onSeekToPCMFrame == nullptr){
+		return false;
+	}
+	return clip->decoder.onSeek != nullptr;
+}
+
+extern "C" int stop(size_t id, AudioContext* context) {
+	SoundClip* clip = context->soundClips->at(id);
+	//held so the device is not stopped twice at once by the end of the clip
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	//a fade out that was going to stop the clip is done once it is stopped
+	if(clip->fadeStop){
+		cancelFade(clip);
+	}
+	clip->resumeWithEngine = false;
+	clip->resumeWithAll = false;
+	if(isClipStarted(clip) && stopClip(clip) != MA_SUCCESS){
+		return -2;
+	}
+	return 0;
+}
+
+static size_t readerRead(ma_decoder* decoder, void* buffer, size_t size){
+	ReaderSource* reader = (ReaderSource*)decoder->pUserData;
+	return reader->read(reader->user, buffer, size);
+}
+
+static ma_bool32 readerSeek(ma_decoder* decoder, int offset, ma_seek_origin origin){
+	ReaderSource* reader = (ReaderSource*)decoder->pUserData;
+	return reader->seek(reader->user, offset, origin == ma_seek_origin_start ? 0 : 1);
+}
+
+//raw decoders do not report a length even though the size of their buffer is known
+static ma_uint64 rawLength(ma_decoder* decoder){
+	return decoder->backend.memory.dataSize / ma_get_bytes_per_frame(decoder->internalFormat, decoder->internalChannels);
+}
+
+//clips without a path are decoded from a buffer or a reader owned by the caller
+static ma_result initDecoder(const char* path, const wchar_t* widePath, const void* data, size_t size, ReaderSource* reader, const DecodedClip* raw, ma_decoder_config* config, ma_decoder* decoder) {
+	if(raw != nullptr){
+		//decoded samples are only converted so they can be shared by many clips without decoding again
+		ma_decoder_config rawConfig = ma_decoder_config_init(ma_format_f32, raw->channels, raw->sampleRate);
+		if(reader != nullptr){
+			//generated samples have no length so the clip plays until it is stopped
+			return ma_decoder_init_raw(readerRead, readerSeek, reader, &rawConfig, config, decoder);
+		}
+		ma_result result = ma_decoder_init_memory_raw(raw->samples, raw->length*sizeof(float), &rawConfig, config, decoder);
+		if(result == MA_SUCCESS){
+			decoder->onGetLengthInPCMFrames = rawLength;
+		}
+		return result;
+	}
+	if(widePath != nullptr){
+		return ma_decoder_init_file_w(widePath, config, decoder);
+	}
+	if(reader != nullptr){
+		//the reader is shared by every attempt so each one starts at the beginning
+		reader->seek(reader->user, 0, 0);
+		return ma_decoder_init(readerRead, readerSeek, reader, config, decoder);
+	}
+	if(path == nullptr){
+		return ma_decoder_init_memory(data, size, config, decoder);
+	}
+	return ma_decoder_init_file(path, config, decoder);
+}
+
+//the result of the last failed decoder is kept per thread so the caller can describe it
+static thread_local ma_result lastDecoderResult = MA_SUCCESS;
+
+extern "C" const char* getDecoderError() {
+	//these are what is left when every decoder rejected the data
+	if(lastDecoderResult == MA_ERROR || lastDecoderResult == MA_NO_BACKEND || lastDecoderResult == MA_INVALID_FILE) {
+		return "no decoder recognized the data";
+	}
+	return ma_result_description(lastDecoderResult);
+}
+
+//without an extension a broken buffer can not be told apart from an unsupported one
+static int decoderError(bool fromFile, ma_result result) {
+	lastDecoderResult = result;
+	if(fromFile && (result == MA_ERROR || result == MA_NO_BACKEND || result == MA_INVALID_FILE)){
+		return -3;
+	}
+	return -1;
+}
+
+//exclusive mode falls back to shared since another device may already hold the output
+static ma_result initPlaybackDevice(AudioContext* context, ma_device_config* config, ma_device* device, bool exclusive) {
+	if(exclusive){
+		config->playback.shareMode = ma_share_mode_exclusive;
+		if(ma_device_init(context->context, config, device) == MA_SUCCESS) {
+			return MA_SUCCESS;
+		}
+	}
+	config->playback.shareMode = ma_share_mode_shared;
+	return ma_device_init(context->context, config, device);
+}
+
+//must be called with the context locked, extra is counted as if it was loaded already
+static size_t memoryUsage(AudioContext* context, const SoundClip* extra) {
+	std::unordered_set<const void*> counted;
+	size_t total = 0;
+	auto count = [&](const SoundClip* clip){
+		if(clip->memoryData != nullptr && counted.insert(clip->memoryData).second){
+			total += clip->memorySize;
+		}
+	};
+	for(auto& pair : *context->soundClips) {
+		count(pair.second);
+	}
+	if(extra != nullptr){
+		count(extra);
+	}
+	return total;
+}
+
+static int loadClip(size_t id, AudioContext* context, const char* path, const wchar_t* widePath, const void* data, size_t size, const ReaderSource* reader, const DecodedClip* raw, AudioDevice* device, const ClipConfig* config) {
 	SoundClip* soundClip = new SoundClip;
 	soundClip->id = id;
-	soundClip->audioDevice = device;
+	soundClip->audioDevice = *device;
+	soundClip->context = context;
 	soundClip->outer = nullptr;
+	soundClip->started = false;
+	soundClip->ending = false;
+	soundClip->stopping = false;
+	soundClip->primed = false;
+	soundClip->ended = false;
+	soundClip->replayOnPlay = true;
+	soundClip->looping = config->looping;
+	soundClip->loopStart = 0;
+	soundClip->loopEnd = 0;
+	soundClip->rangeStart = 0;
+	soundClip->rangeEnd = 0;
+	soundClip->length = 0;
+	soundClip->lengthKnown = false;
+	soundClip->pendingSeek = NO_SEEK;
+	soundClip->cursor = 0;
+	soundClip->refs = 1;
+	soundClip->removed = false;
+	soundClip->callbacks = 0;
+	soundClip->memoryData = data != nullptr ? data : (raw != nullptr ? (const void*)raw->samples : nullptr);
+	soundClip->memorySize = data != nullptr ? size : (raw != nullptr ? raw->length*sizeof(float) : 0);
+	soundClip->silenceThreshold = -1;
+	soundClip->silenceMinFrames = 0;
+	soundClip->silentFrames = 0;
+	soundClip->peak = 0;
+	soundClip->preFaderPeak = 0;
+	soundClip->rms = 0;
+	soundClip->resumeWithEngine = false;
+	soundClip->resumeWithAll = false;
+	soundClip->fading = false;
+	soundClip->fadeStop = false;
+	soundClip->pan = 0;
+	soundClip->tempo = 1;
+	soundClip->bypassProcessor = false;
+	soundClip->spatial = false;
+	for(int axis = 0; axis < 3; axis++) {
+		soundClip->position[axis] = 0;
+	}
+	soundClip->attenuation = 2;
+	soundClip->spatialLeft = 1;
+	soundClip->spatialRight = 1;
+	soundClip->stretchReset = false;
+	clearStretcher(soundClip->stretcher);
+	soundClip->progressInterval = 0;
+	soundClip->progressFrames = 0;
+	soundClip->loops = 0;
+	soundClip->pendingEnd = false;
+	soundClip->pendingFadeStop = false;
+	//the decoder keeps a pointer to the reader so it is copied into the clip
+	ReaderSource* clipReader = nullptr;
+	if(reader != nullptr){
+		soundClip->reader = *reader;
+		clipReader = &soundClip->reader;
+	}
 
 	//creating and configuring decoder
-	if(ma_decoder_init_file(path, NULL, &soundClip->decoder) != MA_SUCCESS) {
+	//miniaudio tries the decoder matching the extension first and then every other decoder on the content
+	//the decoder converts to the format of the context so the device plays its output directly
+	//clips that match the device rate keep the rate of the file so the device can be opened at it
+	EngineConfig engine = context->state->config;
+	ma_decoder_config decoderConfig = ma_decoder_config_init(ma_format_unknown, engine.channels, config->matchDeviceRate ? 0 : engine.sampleRate);
+	ma_result result = initDecoder(path, widePath, data, size, clipReader, raw, &decoderConfig, &soundClip->decoder);
+	if(result != MA_SUCCESS) {
+		//a decoder that failed to initialize has already released its resources
+		delete soundClip;
+		return decoderError(path != nullptr || widePath != nullptr, result);
+	}
+
+	soundClip->codec = detectCodec(&soundClip->decoder);
+
+	//mono files are decoded to both channels so they can be panned
+	if(soundClip->decoder.outputChannels == 1 && engine.channels == 0) {
+		ma_decoder_uninit(&soundClip->decoder);
+		decoderConfig.channels = 2;
+		result = initDecoder(path, widePath, data, size, clipReader, raw, &decoderConfig, &soundClip->decoder);
+		if(result != MA_SUCCESS) {
+			delete soundClip;
+			return decoderError(false, result);
+		}
+	}
+
+	//the range is only known in frames once the output rate of the decoder is
+	ma_uint32 rate = soundClip->decoder.outputSampleRate;
+	soundClip->rangeStart = config->rangeStartMicros * rate / 1000000;
+	soundClip->rangeEnd = config->rangeEndMicros * rate / 1000000;
+	ma_uint64 length = ma_decoder_get_length_in_pcm_frames(&soundClip->decoder);
+	soundClip->length = length;
+	//vorbis and raw streams report zero when they can not tell the length
+	soundClip->lengthKnown = length != 0 || (soundClip->codec >= 1 && soundClip->codec <= 3);
+	if(length != 0 && soundClip->rangeStart >= length){
 		ma_decoder_uninit(&soundClip->decoder);
 		delete soundClip;
-		return -1;
+		return -5;
+	}
+	if(soundClip->rangeStart != 0 && ma_decoder_seek_to_pcm_frame(&soundClip->decoder, soundClip->rangeStart) != MA_SUCCESS){
+		ma_decoder_uninit(&soundClip->decoder);
+		delete soundClip;
+		return -4;
 	}
+	soundClip->cursor = soundClip->rangeStart;
 
 	//configure device
 	soundClip->deviceConfig = ma_device_config_init(ma_device_type_playback);
 	soundClip->deviceConfig.playback.format   = soundClip->decoder.outputFormat;
 	soundClip->deviceConfig.playback.channels = soundClip->decoder.outputChannels;
 	soundClip->deviceConfig.sampleRate        = soundClip->decoder.outputSampleRate;
+	soundClip->deviceConfig.periodSizeInFrames = engine.bufferFrames;
 	soundClip->deviceConfig.dataCallback      = data_callback;
+	soundClip->deviceConfig.stopCallback      = stop_callback;
 	soundClip->deviceConfig.pUserData         = soundClip;
 
-	soundClip->deviceConfig.playback.pDeviceID = &device->id;
+	soundClip->deviceConfig.playback.pDeviceID = &soundClip->audioDevice.id;
 
-	if(ma_device_init(context->context, &soundClip->deviceConfig, &soundClip->device) != MA_SUCCESS) {
+	//decoders can be created in parallel but some backends share state between device inits
+	{
+		std::lock_guard<std::mutex> deviceLock(global);
+		//only exclusive mode lets the device run at the rate of the clip
+		bool exclusive = config->matchDeviceRate || engine.shareMode == 1;
+		result = initPlaybackDevice(context, &soundClip->deviceConfig, &soundClip->device, exclusive);
+	}
+
+	if(result != MA_SUCCESS) {
 		std::cout << "Failed to open playback device" << std::endl;
 		ma_decoder_uninit(&soundClip->decoder);
 		delete soundClip;
 		return -2;
 	}
 
-	soundClip->device.masterVolumeFactor = 1;
+	soundClip->device.masterVolumeFactor = config->volume;
+	if(config->group != nullptr){
+		soundClip->group = *config->group;
+	}
 
 	std::lock_guard<std::mutex> lock(*context->mtx);
+	size_t budget = context->state->memoryBudget;
+	if(budget != 0 && soundClip->memoryData != nullptr && memoryUsage(context, soundClip) > budget){
+		ma_device_uninit(&soundClip->device);
+		ma_decoder_uninit(&soundClip->decoder);
+		delete soundClip;
+		return -7;
+	}
 	context->soundClips->insert({id, soundClip});
 
 	return 0;
 }
 
+extern "C" int load(size_t id, AudioContext* context, const char* path, AudioDevice* device, const ClipConfig* config) {
+	return loadClip(id, context, path, nullptr, nullptr, 0, nullptr, nullptr, device, config);
+}
+
+extern "C" int loadW(size_t id, AudioContext* context, const wchar_t* path, AudioDevice* device, const ClipConfig* config) {
+	return loadClip(id, context, nullptr, path, nullptr, 0, nullptr, nullptr, device, config);
+}
+
+extern "C" int loadMemory(size_t id, AudioContext* context, const void* data, size_t size, AudioDevice* device, const ClipConfig* config) {
+	return loadClip(id, context, nullptr, nullptr, data, size, nullptr, nullptr, device, config);
+}
+
+extern "C" int loadReader(size_t id, AudioContext* context, const ReaderSource* reader, AudioDevice* device, const ClipConfig* config) {
+	return loadClip(id, context, nullptr, nullptr, nullptr, 0, reader, nullptr, device, config);
+}
+
+extern "C" int loadDecoded(size_t id, AudioContext* context, const DecodedClip* clip, AudioDevice* device, const ClipConfig* config) {
+	return loadClip(id, context, nullptr, nullptr, nullptr, 0, nullptr, clip, device, config);
+}
+
+//the reader yields raw f32 frames in the given format instead of an encoded file
+extern "C" int loadGenerator(size_t id, AudioContext* context, const ReaderSource* reader, uint32_t sampleRate, uint32_t channels, AudioDevice* device, const ClipConfig* config) {
+	DecodedClip format{sampleRate, channels, nullptr, 0, nullptr};
+	return loadClip(id, context, nullptr, nullptr, nullptr, 0, reader, &format, device, config);
+}
+
+//progress is reported in steps of at least a percent so the callback does not slow decoding down
+extern "C" int decode(const char* path, const wchar_t* widePath, const void* data, size_t size, ReaderSource* reader, DecodedClip* out, void (*progress)(void*, float), void* user) {
+	ma_decoder decoder;
+	ma_decoder_config config = ma_decoder_config_init(ma_format_f32, 0, 0);
+	ma_result result = initDecoder(path, widePath, data, size, reader, nullptr, &config, &decoder);
+	if(result != MA_SUCCESS) {
+		return decoderError(path != nullptr || widePath != nullptr, result);
+	}
+
+	//the length is not known for every format so the file is read in chunks
+	ma_uint32 channels = decoder.outputChannels;
+	std::vector<float>* samples = new std::vector<float>;
+	std::vector<float> chunk(4096*channels);
+	ma_uint64 length = ma_decoder_get_length_in_pcm_frames(&decoder);
+	ma_uint64 decoded = 0;
+	float reported = 0;
+	if(progress != nullptr && length != 0){
+		progress(user, 0);
+	}
+	ma_uint64 read = 0;
+	do {
+		read = ma_decoder_read_pcm_frames(&decoder, chunk.data(), 4096);
+		samples->insert(samples->end(), chunk.begin(), chunk.begin() + read*channels);
+		decoded += read;
+		if(progress != nullptr && length != 0){
+			float fraction = std::min(1.0f, (float)decoded / length);
+			if(fraction - reported >= 0.01f && fraction < 1){
+				reported = fraction;
+				progress(user, fraction);
+			}
+		}
+	} while(read == 4096);
+	if(progress != nullptr){
+		progress(user, 1);
+	}
+
+	out->sampleRate = decoder.outputSampleRate;
+	out->channels = channels;
+	out->samples = samples->data();
+	out->length = samples->size();
+	out->buffer = samples;
+	ma_decoder_uninit(&decoder);
+	return 0;
+}
+
+extern "C" void releaseDecoded(DecodedClip* clip) {
+	delete (std::vector<float>*)clip->buffer;
+}
+
+//an end that happened before the outer was set is delivered here so it is never lost
 extern "C" void setOuter(size_t id, AudioContext* context, void* outer) {
-	std::lock_guard<std::mutex> lock(*context->mtx);
-	context->soundClips->at(id)->outer = outer;
+	bool ended;
+	{
+		std::lock_guard<std::mutex> ctx_lock(*context->mtx);
+		SoundClip* clip = context->soundClips->at(id);
+		std::lock_guard<std::mutex> lock(clip->mtx);
+		clip->outer = outer;
+		ended = clip->pendingEnd;
+		clip->pendingEnd = false;
+	}
+	if(ended){
+		endCallback(outer);
+	}
 }
 
+//the clip is released without the context locked since it waits for callbacks that may lock it
 extern "C" void removeSound(size_t id, AudioContext* context){
-	std::lock_guard<std::mutex> ctx_lock(*context->mtx);
-	std::lock_guard<std::mutex> lock(context->soundClips->at(id)->mtx);
-	ma_decoder_uninit(&context->soundClips->at(id)->decoder);
-	ma_device_uninit(&context->soundClips->at(id)->device);
-	
-	delete context->soundClips->at(id)->device.pUserData;
-	context->soundClips->erase(id);
+	SoundClip* clip;
+	{
+		std::lock_guard<std::mutex> ctx_lock(*context->mtx);
+		clip = context->soundClips->at(id);
+		context->soundClips->erase(id);
+	}
+	releaseClip(clip);
 }
 
 
@@ -120,6 +636,34 @@ extern "C" size_t getAudioDevices(AudioContext* context, AudioDevice* devices, s
 	return i;
 }
 
+extern "C" size_t getInputDevices(AudioContext* context, AudioDevice* devices, size_t capacity) {
+	ma_device_info* captureDeviceInfos;
+	ma_uint32 captureDeviceCount;
+
+	if(ma_context_get_devices(context->context, NULL, NULL, &captureDeviceInfos, &captureDeviceCount) != MA_SUCCESS) {
+		std::cout << "Failed to retrieve device information" << std::endl;
+		return 0;
+	}
+
+	ma_uint32 i{0};
+	for (; i < captureDeviceCount && i < capacity; ++i) {
+		devices[i] = AudioDevice{
+			captureDeviceInfos[i].id,
+			captureDeviceInfos[i].name
+		};
+	}
+	return i;
+}
+
+extern "C" size_t getInputDeviceCount(AudioContext* context){
+	ma_uint32 captureDeviceCount;
+	if(ma_context_get_devices(context->context, NULL, NULL, NULL, &captureDeviceCount) != MA_SUCCESS) {
+		std::cout << "Failed to retrieve device information" << std::endl;
+		return 0;
+	}
+	return captureDeviceCount;
+}
+
 extern "C" size_t getAudioDeviceCount(AudioContext* context){
 	ma_uint32 playbackDeviceCount;
 	if(ma_context_get_devices(context->context, NULL, &playbackDeviceCount, NULL, NULL) != MA_SUCCESS) {
@@ -129,40 +673,774 @@ extern "C" size_t getAudioDeviceCount(AudioContext* context){
 	return playbackDeviceCount;
 }
 
+//reinitializes the device from deviceConfig while keeping volume and playback state
+//a config the device can not be opened with is replaced by the previous one and false is returned
+static bool reinitClip(AudioContext* context, SoundClip* clip, const ma_device_config& previous, const AudioDevice& previousDevice){
+	bool wasStarted = isClipStarted(clip);
+	float volume = clip->device.masterVolumeFactor;
+	clip->stopping = true;
+	ma_device_uninit(&clip->device);
+	ma_result result = ma_device_init(context->context, &clip->deviceConfig, &clip->device);
+	if(result != MA_SUCCESS){
+		//the config points at the id of the clip device so that is restored as well
+		clip->deviceConfig = previous;
+		clip->audioDevice = previousDevice;
+		ma_device_init(context->context, &clip->deviceConfig, &clip->device);
+	}
+	clip->stopping = false;
+	clip->device.masterVolumeFactor = volume;
+	if(clip->primed && !context->offline){
+		ma_device_start(&clip->device);
+	} else if(wasStarted){
+		startClip(clip);
+	}
+	return result == MA_SUCCESS;
+}
+
+//rates outside of what miniaudio supports are clamped, large ones would not even fit the config
+static ma_uint32 clampRate(double rate){
+	return (ma_uint32)std::min(std::max(rate, (double)MA_MIN_SAMPLE_RATE), (double)MA_MAX_SAMPLE_RATE);
+}
+
+static void setClipRate(AudioContext* context, SoundClip* clip, ma_uint32 rate){
+	ma_device_config previous = clip->deviceConfig;
+	clip->deviceConfig.sampleRate = rate;
+	reinitClip(context, clip, previous, clip->audioDevice);
+}
+
+static void moveClip(AudioContext* context, SoundClip* clip, AudioDevice* device){
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	ma_device_config previous = clip->deviceConfig;
+	AudioDevice previousDevice = clip->audioDevice;
+	clip->audioDevice = *device;
+	clip->deviceConfig.playback.pDeviceID = &clip->audioDevice.id;
+	reinitClip(context, clip, previous, previousDevice);
+}
+
 extern "C" void setAudioDevice(size_t id, AudioContext* context, AudioDevice* device){
-	std::lock_guard<std::mutex> lock(context->soundClips->at(id)->mtx);
-	ma_device_uninit(&context->soundClips->at(id)->device);
-	context->soundClips->at(id)->audioDevice = device;
-	context->soundClips->at(id)->deviceConfig.playback.pDeviceID = &device->id;
-	ma_device_init(context->context, &context->soundClips->at(id)->deviceConfig, &context->soundClips->at(id)->device);
+	moveClip(context, context->soundClips->at(id), device);
 }
 
-extern "C" AudioDevice getDefaultAudioDevice(AudioContext* context){
+//the name is left out since it points into a device list that is replaced on every enumeration
+extern "C" void getClipDevice(size_t id, AudioContext* context, AudioDevice* device){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	*device = AudioDevice{clip->audioDevice.id, nullptr};
+}
+
+extern "C" void setContextAudioDevice(AudioContext* context, AudioDevice* device){
+	std::lock_guard<std::mutex> lock(*context->mtx);
+	for(auto& pair : *context->soundClips) {
+		moveClip(context, pair.second, device);
+	}
+}
+
+extern "C" bool tryGetDefaultAudioDevice(AudioContext* context, AudioDevice* device){
 	ma_device_info* playbackDeviceInfos;
 	ma_uint32 playbackDeviceCount;
 	if(ma_context_get_devices(context->context, &playbackDeviceInfos, &playbackDeviceCount, NULL, NULL) != MA_SUCCESS) {
 		std::cout << "Failed to retrieve device information" << std::endl;
+		return false;
+	}
+	if(playbackDeviceCount == 0){
+		return false;
 	}
 	for(size_t i{0}; i<playbackDeviceCount; ++i){
 		if(playbackDeviceInfos[i].isDefault) {
-			return AudioDevice{
+			*device = AudioDevice{
 				playbackDeviceInfos[i].id,
 				playbackDeviceInfos[i].name
 			};
+			return true;
 		}
 	}
-	return AudioDevice{
+	*device = AudioDevice{
 		playbackDeviceInfos[0].id,
 		playbackDeviceInfos[0].name
 	};
+	return true;
+}
+
+extern "C" AudioDevice getDefaultAudioDevice(AudioContext* context){
+	AudioDevice device{ma_device_id{}, ""};
+	tryGetDefaultAudioDevice(context, &device);
+	return device;
+}
+
+//the device is opened without being started since only its internal format is needed
+extern "C" bool getDeviceFormat(AudioContext* context, AudioDevice* device, bool capture, DeviceFormat* format){
+	ma_device_config config = ma_device_config_init(capture ? ma_device_type_capture : ma_device_type_playback);
+	if(capture){
+		config.capture.pDeviceID = &device->id;
+	} else {
+		config.playback.pDeviceID = &device->id;
+	}
+	std::unique_ptr<ma_device> probe{new ma_device};
+	{
+		std::lock_guard<std::mutex> lock(global);
+		if(ma_device_init(context->context, &config, probe.get()) != MA_SUCCESS){
+			return false;
+		}
+	}
+	if(capture){
+		format->sampleRate = probe->capture.internalSampleRate;
+		format->channels = probe->capture.internalChannels;
+	} else {
+		format->sampleRate = probe->playback.internalSampleRate;
+		format->channels = probe->playback.internalChannels;
+	}
+	ma_device_uninit(probe.get());
+	return true;
+}
+
+//the device is opened the way a clip would open it and the latency is the whole device buffer
+extern "C" uint64_t getLatency(AudioContext* context, AudioDevice* device){
+	EngineConfig engine = context->state->config;
+	ma_device_config config = ma_device_config_init(ma_device_type_playback);
+	config.playback.pDeviceID = &device->id;
+	config.periodSizeInFrames = engine.bufferFrames;
+	std::unique_ptr<ma_device> probe{new ma_device};
+	{
+		std::lock_guard<std::mutex> lock(global);
+		if(initPlaybackDevice(context, &config, probe.get(), engine.shareMode == 1) != MA_SUCCESS){
+			return 0;
+		}
+	}
+	ma_uint64 frames = (ma_uint64)probe->playback.internalPeriodSizeInFrames*probe->playback.internalPeriods;
+	ma_uint32 rate = probe->playback.internalSampleRate;
+	ma_device_uninit(probe.get());
+	return rate == 0 ? 0 : frames*1000000/rate;
+}
+
+extern "C" int getShareMode(AudioContext* context){
+	return context->state->config.shareMode;
 }
 
 extern "C" uint64_t getDuration(size_t id, AudioContext* context){
-	uint64_t sampleRate{context->soundClips->at(id)->device.sampleRate};
-	uint64_t duration{ma_decoder_get_length_in_pcm_frames(&context->soundClips->at(id)->decoder)};
-	return duration/(sampleRate/1000);
+	return clipDuration(context->soundClips->at(id));
+}
+
+extern "C" uint64_t getPosition(size_t id, AudioContext* context){
+	return clipPosition(context->soundClips->at(id));
+}
+
+extern "C" int getState(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	return clipState(clip);
 }
 
 extern "C" bool isPlaying(size_t id, AudioContext* context){
-  	return ma_device_is_started(&context->soundClips->at(id)->device);
+  	return isClipStarted(context->soundClips->at(id));
+}
+
+extern "C" void setMemoryBudget(AudioContext* context, size_t bytes){
+	context->state->memoryBudget = bytes;
+}
+
+extern "C" ContextUsage getStats(AudioContext* context){
+	std::lock_guard<std::mutex> lock(*context->mtx);
+	size_t playing = 0;
+	for(auto& pair : *context->soundClips) {
+		if(isClipStarted(pair.second)){
+			playing++;
+		}
+	}
+	return ContextUsage{context->soundClips->size(), playing, memoryUsage(context, nullptr), context->state->memoryBudget.load()};
+}
+
+extern "C" bool advance(AudioContext* context, uint64_t micros) {
+	if(!context->offline){
+		return false;
+	}
+
+	//clips are advanced together in slices so groups ducking under each other hear each other in time
+	const uint64_t slice = 10000;
+	std::vector<void*> looped;
+	std::vector<void*> fadeStopped;
+	std::vector<void*> ended;
+	{
+		std::lock_guard<std::mutex> ctx_lock(*context->mtx);
+		int64_t start = context->state->clock;
+		for(uint64_t elapsed = 0; elapsed < micros; elapsed += slice) {
+			uint64_t next = std::min(elapsed + slice, micros);
+			context->state->clock = start + elapsed;
+			for(auto& pair : *context->soundClips) {
+				SoundClip* clip = pair.second;
+				std::lock_guard<std::mutex> lock(clip->mtx);
+				if(!clip->started){
+					continue;
+				}
+
+				ma_uint32 frameSize = ma_get_bytes_per_frame(clip->device.playback.format, clip->device.playback.channels);
+				std::vector<char> buffer(1024 * frameSize);
+				ma_uint64 remaining = next * clip->device.sampleRate / 1000000 - elapsed * clip->device.sampleRate / 1000000;
+				while(remaining > 0 && clip->started) {
+					ma_uint32 frames = (ma_uint32)std::min<ma_uint64>(remaining, 1024);
+					data_callback(&clip->device, buffer.data(), NULL, frames);
+					remaining -= frames;
+				}
+			}
+		}
+		context->state->clock = start + micros;
+
+		for(auto& pair : *context->soundClips) {
+			SoundClip* clip = pair.second;
+			std::lock_guard<std::mutex> lock(clip->mtx);
+			if(clip->loops != 0 && clip->outer != nullptr){
+				looped.push_back(clip->outer);
+			}
+			if(clip->pendingFadeStop.exchange(false) && clip->outer != nullptr){
+				fadeStopped.push_back(clip->outer);
+			}
+			if(clip->pendingEnd && clip->outer != nullptr){
+				clip->pendingEnd = false;
+				ended.push_back(clip->outer);
+			}
+		}
+	}
+
+	//callbacks run without any locks held so they can control other clips
+	for(void* outer : looped) {
+		loopCallback(outer);
+	}
+	for(void* outer : fadeStopped) {
+		fadeStopCallback(outer);
+	}
+	for(void* outer : ended) {
+		endCallback(outer);
+	}
+	return true;
+}
+
+extern "C" void setProgressCallback(void (*progress_callback)(void*)){
+	progressCallback = progress_callback;
+}
+
+extern "C" void setLoopCallback(void (*loop_callback)(void*)){
+	loopCallback = loop_callback;
+}
+
+extern "C" void setFadeStopCallback(void (*fade_stop_callback)(void*)){
+	fadeStopCallback = fade_stop_callback;
+}
+
+extern "C" uint64_t takeLoops(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->loops.exchange(0);
+}
+
+extern "C" void setDeviceLostCallback(void (*device_lost_callback)(void*)){
+	deviceLostCallback = device_lost_callback;
+}
+
+//an interval of 0 turns progress reports off
+extern "C" void setProgressInterval(size_t id, AudioContext* context, uint64_t micros){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	clip->progressFrames = 0;
+	clip->progressInterval = std::max<ma_uint64>(micros * clip->decoder.outputSampleRate / 1000000, micros == 0 ? 0 : 1);
+}
+
+extern "C" int getCodec(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->codec;
+}
+
+extern "C" ClipInfo getInfo(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	return ClipInfo{
+		clip->decoder.internalSampleRate,
+		clip->decoder.internalChannels,
+		(int)clip->decoder.internalFormat
+	};
+}
+
+extern "C" void* openBlockTap(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	std::shared_ptr<BlockTap> tap = std::make_shared<BlockTap>();
+	tap->samples.resize(TAP_SAMPLES);
+	tap->lengths.resize(MAX_QUEUED_BLOCKS);
+	tap->writeBlock = 0;
+	tap->writeSample = 0;
+	tap->readBlock = 0;
+	tap->readSample = 0;
+	tap->ended = false;
+	endBlockTap(clip);
+	std::atomic_store(&clip->tap, tap);
+	return new std::shared_ptr<BlockTap>(tap);
+}
+
+//returns the length of the next block and only consumes it if it fits in output
+extern "C" int64_t nextBlock(void* tap, float* output, size_t capacity){
+	BlockTap* blockTap = static_cast<std::shared_ptr<BlockTap>*>(tap)->get();
+	size_t block = blockTap->readBlock;
+	{
+		std::unique_lock<std::mutex> lock(blockTap->mtx);
+		//a notification can slip in before the wait since the audio thread does not lock, the timeout bounds that
+		while(blockTap->writeBlock == block && !blockTap->ended){
+			blockTap->cv.wait_for(lock, std::chrono::milliseconds(10));
+		}
+	}
+	if(blockTap->writeBlock == block){
+		return -1;
+	}
+	size_t length = blockTap->lengths[block % MAX_QUEUED_BLOCKS];
+	if(length <= capacity){
+		size_t sample = blockTap->readSample;
+		for(size_t i = 0; i < length; i++) {
+			output[i] = blockTap->samples[(sample + i) % TAP_SAMPLES];
+		}
+		blockTap->readSample = sample + length;
+		blockTap->readBlock = block + 1;
+	}
+	return length;
+}
+
+extern "C" void closeBlockTap(void* tap){
+	delete static_cast<std::shared_ptr<BlockTap>*>(tap);
+}
+
+extern "C" ClipSnapshot getSnapshot(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	return ClipSnapshot{
+		clipVolume(clip),
+		clipDuration(clip),
+		clipState(clip),
+		clip->pan,
+		clipSpeed(clip),
+		clip->looping,
+		clipPosition(clip)
+	};
+}
+
+extern "C" bool isResampling(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	//the decoder converts files to the rate of the context and the device converts to the rate of the hardware
+	return clip->decoder.outputSampleRate != clip->decoder.internalSampleRate || clip->device.sampleRate != clip->device.playback.internalSampleRate;
+}
+
+extern "C" void setInstant(size_t id, AudioContext* context, bool instant){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	if(context->offline || clip->primed == instant){
+		return;
+	}
+	if(instant){
+		clip->started = ma_device_is_started(&clip->device);
+		clip->primed = true;
+		ma_device_start(&clip->device);
+	} else {
+		clip->primed = false;
+		if(!clip->started){
+			clip->stopping = true;
+			ma_device_stop(&clip->device);
+			clip->stopping = false;
+		}
+	}
+}
+
+extern "C" void setReplayOnPlay(size_t id, AudioContext* context, bool replay){
+	context->soundClips->at(id)->replayOnPlay = replay;
+}
+
+extern "C" void setLooping(size_t id, AudioContext* context, bool looping){
+	context->soundClips->at(id)->looping = looping;
+}
+
+extern "C" bool isLooping(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->looping;
+}
+
+//an end of zero clears the region, ends past the end of the clip are clamped to it
+//both are relative to the start of the range
+extern "C" bool setLoopRegion(size_t id, AudioContext* context, uint64_t startMicros, uint64_t endMicros){
+	SoundClip* clip = context->soundClips->at(id);
+	if(endMicros == 0){
+		clip->loopEnd = 0;
+		return true;
+	}
+	ma_uint64 start = clip->rangeStart + startMicros * clip->decoder.outputSampleRate / 1000000;
+	ma_uint64 end = clip->rangeStart + endMicros * clip->decoder.outputSampleRate / 1000000;
+	ma_uint64 length = clipEnd(clip);
+	if((length != 0 || clip->lengthKnown) && end > length){
+		end = length;
+	}
+	if(end <= start){
+		return false;
+	}
+	clip->loopEnd = 0;
+	clip->loopStart = start;
+	clip->loopEnd = end;
+	return true;
+}
+
+extern "C" void setProcessor(AudioContext* context, void (*processor)(void*, float*, ma_uint32, ma_uint32), void* user){
+	std::lock_guard<std::mutex> lock(*context->mtx);
+	context->processorUser = user;
+	context->processor = processor;
+}
+
+//a negative threshold disables silence detection
+extern "C" void setSilenceDetect(size_t id, AudioContext* context, float threshold, uint64_t minMillis){
+	SoundClip* clip = context->soundClips->at(id);
+	clip->silenceMinFrames = minMillis * clip->device.sampleRate / 1000;
+	clip->silentFrames = 0;
+	clip->silenceThreshold = threshold;
+}
+
+extern "C" bool isAudible(size_t id, AudioContext* context, float threshold){
+	SoundClip* clip = context->soundClips->at(id);
+	return isClipStarted(clip) && clip->peak * clip->device.masterVolumeFactor > threshold;
+}
+
+//a rate of 0 restores the native rate of the decoder
+extern "C" void setPlaybackRate(size_t id, AudioContext* context, uint32_t rate){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	setClipRate(context, clip, rate == 0 ? clip->decoder.outputSampleRate : clampRate(rate));
+}
+
+extern "C" uint32_t getPlaybackRate(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->device.sampleRate;
+}
+
+extern "C" void setSpeed(size_t id, AudioContext* context, float speed){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	setClipRate(context, clip, clampRate(clip->decoder.outputSampleRate * (double)speed));
+}
+
+extern "C" float getSpeed(size_t id, AudioContext* context){
+	return clipSpeed(context->soundClips->at(id));
+}
+
+//any tempo other than one runs the clip through the stretcher
+extern "C" void setTempo(size_t id, AudioContext* context, float tempo){
+	if(std::isnan(tempo)){
+		return;
+	}
+	SoundClip* clip = context->soundClips->at(id);
+	clip->stretchReset = true;
+	clip->tempo = std::min(std::max(tempo, 0.25f), 4.0f);
+}
+
+extern "C" float getTempo(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->tempo;
+}
+
+//the first position turns the clip into a spatial one
+extern "C" void setPosition3d(size_t id, AudioContext* context, float x, float y, float z){
+	SoundClip* clip = context->soundClips->at(id);
+	clip->position[0] = x;
+	clip->position[1] = y;
+	clip->position[2] = z;
+	clip->spatial = true;
+}
+
+extern "C" void clearPosition3d(size_t id, AudioContext* context){
+	context->soundClips->at(id)->spatial = false;
+}
+
+extern "C" void setAttenuation(size_t id, AudioContext* context, int model){
+	context->soundClips->at(id)->attenuation = model;
+}
+
+extern "C" int getAttenuation(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->attenuation;
+}
+
+extern "C" void setListenerPosition(AudioContext* context, float x, float y, float z){
+	Listener& listener = context->state->listener;
+	listener.position[0] = x;
+	listener.position[1] = y;
+	listener.position[2] = z;
+}
+
+extern "C" void setListenerDirection(AudioContext* context, float x, float y, float z){
+	Listener& listener = context->state->listener;
+	listener.direction[0] = x;
+	listener.direction[1] = y;
+	listener.direction[2] = z;
+}
+
+extern "C" void setPan(size_t id, AudioContext* context, float pan){
+	context->soundClips->at(id)->pan = std::min(std::max(pan, -1.0f), 1.0f);
+}
+
+extern "C" float getPan(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->pan;
+}
+
+extern "C" void setProcessorBypass(size_t id, AudioContext* context, bool bypass){
+	context->soundClips->at(id)->bypassProcessor = bypass;
+}
+
+extern "C" bool getProcessorBypass(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->bypassProcessor;
+}
+
+//volume, speed, looping, the loop region and silence detection are kept, the effects applied on top of the decoded signal go back to their default
+extern "C" void clearEffects(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	std::lock_guard<std::mutex> lock(clip->mtx);
+	cancelFade(clip);
+	clip->pan = 0;
+	if(clip->tempo != 1){
+		clip->stretchReset = true;
+		clip->tempo = 1;
+	}
+	clip->bypassProcessor = false;
+	clip->spatial = false;
+	clip->attenuation = 2;
+}
+
+extern "C" float getPreFaderLevel(size_t id, AudioContext* context){
+	return context->soundClips->at(id)->preFaderPeak;
+}
+
+extern "C" float getPostFaderLevel(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	return clip->peak * clip->device.masterVolumeFactor;
+}
+
+//the device volume is applied after the callback so it is folded in here
+extern "C" float getRmsLevel(size_t id, AudioContext* context){
+	SoundClip* clip = context->soundClips->at(id);
+	return clip->rms * clip->device.masterVolumeFactor;
+}
+
+extern "C" void copySettings(size_t id, AudioContext* context, size_t otherId, AudioContext* otherContext){
+	SoundClip* clip = context->soundClips->at(id);
+	SoundClip* other = otherContext->soundClips->at(otherId);
+	if(clip == other){
+		return;
+	}
+	std::lock(clip->mtx, other->mtx);
+	std::lock_guard<std::mutex> lock(clip->mtx, std::adopt_lock);
+	std::lock_guard<std::mutex> otherLock(other->mtx, std::adopt_lock);
+
+	//a fade in progress is not copied, only the volume it is at
+	cancelFade(clip);
+	clip->device.masterVolumeFactor = clipVolume(other);
+	clip->replayOnPlay = other->replayOnPlay.load();
+	clip->silenceThreshold = other->silenceThreshold.load();
+	clip->silenceMinFrames = other->silenceMinFrames * clip->device.sampleRate / std::max<ma_uint32>(other->device.sampleRate, 1);
+	clip->pan = other->pan.load();
+	clip->bypassProcessor = other->bypassProcessor.load();
+	clip->looping = other->looping.load();
+	for(int axis = 0; axis < 3; axis++){
+		clip->position[axis] = other->position[axis].load();
+	}
+	clip->spatial = other->spatial.load();
+	clip->attenuation = other->attenuation.load();
+	if(clip->tempo != other->tempo){
+		clip->stretchReset = true;
+		clip->tempo = other->tempo.load();
+	}
+
+	//loop regions are copied in time relative to the start of each range and clamped like setLoopRegion
+	ma_uint64 otherLoopEnd = other->loopEnd;
+	ma_uint32 otherRate = other->decoder.outputSampleRate;
+	if(otherLoopEnd == 0 || otherRate == 0){
+		setLoopRegion(id, context, 0, 0);
+	} else {
+		uint64_t startMicros = (other->loopStart - other->rangeStart)*1000000/otherRate;
+		uint64_t endMicros = (otherLoopEnd - other->rangeStart)*1000000/otherRate;
+		if(!setLoopRegion(id, context, startMicros, endMicros)){
+			setLoopRegion(id, context, 0, 0);
+		}
+	}
+
+	//the playback rate is copied relative to the native rate of each file
+	double ratio = (double)other->deviceConfig.sampleRate / other->decoder.outputSampleRate;
+	ma_uint32 rate = clampRate(clip->decoder.outputSampleRate * ratio);
+	if(rate != clip->deviceConfig.sampleRate){
+		setClipRate(context, clip, rate);
+	}
+}
+
+//a null device records from the default input device
+extern "C" Recorder* openRecorder(AudioContext* context, AudioDevice* device){
+	Recorder* recorder = new Recorder;
+	ma_device_config config = ma_device_config_init(ma_device_type_capture);
+	config.capture.format = ma_format_f32;
+	config.capture.pDeviceID = device == nullptr ? NULL : &device->id;
+	config.dataCallback = capture_callback;
+	config.pUserData = recorder;
+	if(ma_device_init(context->context, &config, &recorder->device) != MA_SUCCESS){
+		std::cout << "Failed to open capture device" << std::endl;
+		delete recorder;
+		return nullptr;
+	}
+	std::lock_guard<std::mutex> lock(*context->mtx);
+	context->state->recorders.push_back(recorder);
+	return recorder;
+}
+
+extern "C" bool startRecorder(Recorder* recorder){
+	return ma_device_start(&recorder->device) == MA_SUCCESS;
+}
+
+extern "C" void stopRecorder(Recorder* recorder){
+	ma_device_stop(&recorder->device);
+}
+
+extern "C" bool isRecording(Recorder* recorder){
+	return ma_device_is_started(&recorder->device);
+}
+
+extern "C" uint32_t getRecorderChannels(Recorder* recorder){
+	return recorder->device.capture.channels;
+}
+
+extern "C" uint32_t getRecorderSampleRate(Recorder* recorder){
+	return recorder->device.sampleRate;
+}
+
+extern "C" size_t getRecordedLength(Recorder* recorder){
+	std::lock_guard<std::mutex> lock(recorder->mtx);
+	return recorder->samples.size();
+}
+
+//samples only ever grow so taking at most capacity never loses any
+extern "C" size_t takeRecorded(Recorder* recorder, float* output, size_t capacity){
+	std::lock_guard<std::mutex> lock(recorder->mtx);
+	size_t len = std::min(capacity, recorder->samples.size());
+	std::copy(recorder->samples.begin(), recorder->samples.begin() + len, output);
+	recorder->samples.erase(recorder->samples.begin(), recorder->samples.begin() + len);
+	return len;
+}
+
+extern "C" void closeRecorder(AudioContext* context, Recorder* recorder){
+	{
+		std::lock_guard<std::mutex> lock(*context->mtx);
+		std::vector<Recorder*>& recorders = context->state->recorders;
+		recorders.erase(std::remove(recorders.begin(), recorders.end(), recorder), recorders.end());
+	}
+	ma_device_uninit(&recorder->device);
+	delete recorder;
+}
+
+extern "C" void pauseEngine(AudioContext* context){
+	std::lock_guard<std::mutex> lock(*context->mtx);
+	if(context->state->paused){
+		return;
+	}
+	context->state->paused = true;
+	for(auto& pair : *context->soundClips) {
+		SoundClip* clip = pair.second;
+		std::lock_guard<std::mutex> clip_lock(clip->mtx);
+		if(isClipStarted(clip)){
+			clip->resumeWithEngine = true;
+			stopClip(clip);
+		}
+	}
+}
+
+extern "C" void resumeEngine(AudioContext* context){
+	std::lock_guard<std::mutex> ctx_lock(*context->mtx);
+	if(!context->state->paused){
+		return;
+	}
+	context->state->paused = false;
+	for(auto& pair : *context->soundClips) {
+		SoundClip* clip = pair.second;
+		std::lock_guard<std::mutex> lock(clip->mtx);
+		if(clip->resumeWithEngine){
+			clip->resumeWithEngine = false;
+			startClip(clip);
+		}
+	}
+}
+
+//unlike pausing the engine this does not hold back clips that are played afterwards
+extern "C" void stopAll(AudioContext* context){
+	std::lock_guard<std::mutex> ctx_lock(*context->mtx);
+	for(auto& pair : *context->soundClips) {
+		SoundClip* clip = pair.second;
+		std::lock_guard<std::mutex> lock(clip->mtx);
+		if(isClipStarted(clip)){
+			clip->resumeWithAll = true;
+			stopClip(clip);
+		}
+	}
+}
+
+//clips stopped by stopAll while the engine is paused are handed to the engine instead
+extern "C" void resumeAll(AudioContext* context){
+	std::lock_guard<std::mutex> ctx_lock(*context->mtx);
+	for(auto& pair : *context->soundClips) {
+		SoundClip* clip = pair.second;
+		if(!clip->resumeWithAll.exchange(false)){
+			continue;
+		}
+		std::lock_guard<std::mutex> lock(clip->mtx);
+		if(context->state->paused){
+			clip->resumeWithEngine = true;
+		} else if(!isClipStarted(clip)){
+			startClip(clip);
+		}
+	}
+}
+
+extern "C" bool isEnginePaused(AudioContext* context){
+	return context->state->paused;
+}
+
+extern "C" void* createGroup(){
+	std::shared_ptr<SoundGroup> group = std::make_shared<SoundGroup>();
+	group->volume = 1;
+	group->muted = false;
+	group->activity = std::make_shared<DuckTrigger>();
+	group->activity->lastActive = std::numeric_limits<int64_t>::min() / 2;
+	group->duckGain = 1;
+	group->duckAttack = 0;
+	group->duckRelease = 0;
+	group->duckDepth = 0;
+	group->duckUpdated = std::numeric_limits<int64_t>::min();
+	return new std::shared_ptr<SoundGroup>(group);
+}
+
+//a null trigger stops ducking right away
+extern "C" void setGroupDuck(void* group, void* trigger, float gain, uint64_t attackMicros, uint64_t releaseMicros){
+	SoundGroup* soundGroup = static_cast<std::shared_ptr<SoundGroup>*>(group)->get();
+	soundGroup->duckGain = gain;
+	soundGroup->duckAttack = attackMicros;
+	soundGroup->duckRelease = releaseMicros;
+	if(trigger == nullptr){
+		soundGroup->duckDepth = 0;
+		std::atomic_store(&soundGroup->trigger, std::shared_ptr<DuckTrigger>());
+		return;
+	}
+	std::atomic_store(&soundGroup->trigger, (*static_cast<std::shared_ptr<SoundGroup>*>(trigger))->activity);
+}
+
+extern "C" void setGroupVolume(void* group, float volume){
+	(*static_cast<std::shared_ptr<SoundGroup>*>(group))->volume = volume;
+}
+
+extern "C" float getGroupVolume(void* group){
+	return (*static_cast<std::shared_ptr<SoundGroup>*>(group))->volume;
+}
+
+//muting keeps the volume so unmuting restores it
+extern "C" void setGroupMuted(void* group, bool muted){
+	(*static_cast<std::shared_ptr<SoundGroup>*>(group))->muted = muted;
+}
+
+extern "C" bool isGroupMuted(void* group){
+	return (*static_cast<std::shared_ptr<SoundGroup>*>(group))->muted;
+}
+
+extern "C" void setMasterVolume(AudioContext* context, float volume){
+	context->state->masterVolume = volume;
+}
+
+extern "C" float getMasterVolume(AudioContext* context){
+	return context->state->masterVolume;
+}
+
+//clips routed to the group keep it alive until they are removed
+extern "C" void releaseGroup(void* group){
+	delete static_cast<std::shared_ptr<SoundGroup>*>(group);
 }
@@ -1,26 +1,70 @@
 #pragma once
 #include "AudioPlayer.h"
 #include <vector>
+#include <unordered_set>
 #include <iostream>
 #include <functional>
 #include <chrono>
+#include <algorithm>
 #include <mutex>
 
 extern "C" AudioContext init(void (*end_callback)(void*));
 
+extern "C" AudioContext initConfig(void (*end_callback)(void*), const EngineConfig* config);
+
+extern "C" int getBackend(AudioContext* context);
+
+extern "C" const char* getVersion();
+
+extern "C" size_t getEnabledBackends(int32_t* backends, size_t capacity);
+
+extern "C" size_t getSoundCount(AudioContext* context);
+
+extern "C" void setMemoryBudget(AudioContext* context, size_t bytes);
+
+extern "C" ContextUsage getStats(AudioContext* context);
+
+extern "C" AudioContext initNull(void (*end_callback)(void*));
+
+extern "C" AudioContext initOffline(void (*end_callback)(void*));
+
 extern "C" void uninit(AudioContext* context);
 
 extern "C" void setVolume(size_t id, AudioContext* context, float value);
 
 extern "C" float getVolume(size_t id, AudioContext* context);
 
-extern "C" void play(size_t id, AudioContext* context);
+extern "C" void fade(size_t id, AudioContext* context, float from, float to, uint64_t micros, bool stop, int curve);
+
+extern "C" int play(size_t id, AudioContext* context);
+
+extern "C" int reset(size_t id, AudioContext* context);
+
+extern "C" int replay(size_t id, AudioContext* context);
+
+extern "C" bool seek(size_t id, AudioContext* context, uint64_t micros);
 
-extern "C" void reset(size_t id, AudioContext* context);
+extern "C" bool isSeekable(size_t id, AudioContext* context);
 
-extern "C" void stop(size_t id, AudioContext* context);
+extern "C" int stop(size_t id, AudioContext* context);
 
-extern "C" int load(size_t id, AudioContext* context, const char* path, AudioDevice* device);
+extern "C" int load(size_t id, AudioContext* context, const char* path, AudioDevice* device, const ClipConfig* config);
+
+extern "C" int loadW(size_t id, AudioContext* context, const wchar_t* path, AudioDevice* device, const ClipConfig* config);
+
+extern "C" int loadMemory(size_t id, AudioContext* context, const void* data, size_t size, AudioDevice* device, const ClipConfig* config);
+
+extern "C" int decode(const char* path, const wchar_t* widePath, const void* data, size_t size, ReaderSource* reader, DecodedClip* out, void (*progress)(void*, float), void* user);
+
+extern "C" const char* getDecoderError();
+
+extern "C" void releaseDecoded(DecodedClip* clip);
+
+extern "C" int loadReader(size_t id, AudioContext* context, const ReaderSource* reader, AudioDevice* device, const ClipConfig* config);
+
+extern "C" int loadDecoded(size_t id, AudioContext* context, const DecodedClip* clip, AudioDevice* device, const ClipConfig* config);
+
+extern "C" int loadGenerator(size_t id, AudioContext* context, const ReaderSource* reader, uint32_t sampleRate, uint32_t channels, AudioDevice* device, const ClipConfig* config);
 
 extern "C" void setOuter(size_t id, AudioContext* context, void* outer);
 
@@ -30,10 +74,162 @@ extern "C" size_t getAudioDevices(AudioContext* context, AudioDevice* devices, s
 
 extern "C" size_t getAudioDeviceCount(AudioContext* context);
 
+extern "C" size_t getInputDevices(AudioContext* context, AudioDevice* devices, size_t capacity);
+
+extern "C" size_t getInputDeviceCount(AudioContext* context);
+
 extern "C" void setAudioDevice(size_t id, AudioContext* context, AudioDevice* device);
 
+extern "C" void getClipDevice(size_t id, AudioContext* context, AudioDevice* device);
+
+extern "C" void setContextAudioDevice(AudioContext* context, AudioDevice* device);
+
 extern "C" AudioDevice getDefaultAudioDevice(AudioContext* context);
 
+extern "C" bool tryGetDefaultAudioDevice(AudioContext* context, AudioDevice* device);
+
+extern "C" bool getDeviceFormat(AudioContext* context, AudioDevice* device, bool capture, DeviceFormat* format);
+
+extern "C" uint64_t getLatency(AudioContext* context, AudioDevice* device);
+
+extern "C" int getShareMode(AudioContext* context);
+
 extern "C" uint64_t getDuration(size_t id, AudioContext* context);
 
+extern "C" uint64_t getPosition(size_t id, AudioContext* context);
+
+extern "C" int getState(size_t id, AudioContext* context);
+
 extern "C" bool isPlaying(size_t id, AudioContext* context);
+
+extern "C" bool advance(AudioContext* context, uint64_t micros);
+
+extern "C" void setProgressCallback(void (*progress_callback)(void*));
+
+extern "C" void setLoopCallback(void (*loop_callback)(void*));
+
+extern "C" uint64_t takeLoops(size_t id, AudioContext* context);
+
+extern "C" void setFadeStopCallback(void (*fade_stop_callback)(void*));
+
+extern "C" void setDeviceLostCallback(void (*device_lost_callback)(void*));
+
+extern "C" void setProgressInterval(size_t id, AudioContext* context, uint64_t micros);
+
+extern "C" int getCodec(size_t id, AudioContext* context);
+
+extern "C" ClipInfo getInfo(size_t id, AudioContext* context);
+
+extern "C" void* openBlockTap(size_t id, AudioContext* context);
+
+extern "C" int64_t nextBlock(void* tap, float* output, size_t capacity);
+
+extern "C" void closeBlockTap(void* tap);
+
+extern "C" ClipSnapshot getSnapshot(size_t id, AudioContext* context);
+
+extern "C" bool isResampling(size_t id, AudioContext* context);
+
+extern "C" void setInstant(size_t id, AudioContext* context, bool instant);
+
+extern "C" void setReplayOnPlay(size_t id, AudioContext* context, bool replay);
+
+extern "C" void setLooping(size_t id, AudioContext* context, bool looping);
+
+extern "C" bool isLooping(size_t id, AudioContext* context);
+
+extern "C" bool setLoopRegion(size_t id, AudioContext* context, uint64_t startMicros, uint64_t endMicros);
+
+extern "C" void setProcessor(AudioContext* context, void (*processor)(void*, float*, ma_uint32, ma_uint32), void* user);
+
+extern "C" void setSilenceDetect(size_t id, AudioContext* context, float threshold, uint64_t minMillis);
+
+extern "C" bool isAudible(size_t id, AudioContext* context, float threshold);
+
+extern "C" void setPlaybackRate(size_t id, AudioContext* context, uint32_t rate);
+
+extern "C" uint32_t getPlaybackRate(size_t id, AudioContext* context);
+
+extern "C" void setSpeed(size_t id, AudioContext* context, float speed);
+
+extern "C" float getSpeed(size_t id, AudioContext* context);
+
+extern "C" void setTempo(size_t id, AudioContext* context, float tempo);
+
+extern "C" float getTempo(size_t id, AudioContext* context);
+
+extern "C" void setPosition3d(size_t id, AudioContext* context, float x, float y, float z);
+
+extern "C" void clearPosition3d(size_t id, AudioContext* context);
+
+extern "C" void setAttenuation(size_t id, AudioContext* context, int model);
+
+extern "C" int getAttenuation(size_t id, AudioContext* context);
+
+extern "C" void setListenerPosition(AudioContext* context, float x, float y, float z);
+
+extern "C" void setListenerDirection(AudioContext* context, float x, float y, float z);
+
+extern "C" void setPan(size_t id, AudioContext* context, float pan);
+
+extern "C" float getPan(size_t id, AudioContext* context);
+
+extern "C" void setProcessorBypass(size_t id, AudioContext* context, bool bypass);
+
+extern "C" bool getProcessorBypass(size_t id, AudioContext* context);
+
+extern "C" void clearEffects(size_t id, AudioContext* context);
+
+extern "C" float getPreFaderLevel(size_t id, AudioContext* context);
+
+extern "C" float getPostFaderLevel(size_t id, AudioContext* context);
+
+extern "C" float getRmsLevel(size_t id, AudioContext* context);
+
+extern "C" void copySettings(size_t id, AudioContext* context, size_t otherId, AudioContext* otherContext);
+
+extern "C" Recorder* openRecorder(AudioContext* context, AudioDevice* device);
+
+extern "C" bool startRecorder(Recorder* recorder);
+
+extern "C" void stopRecorder(Recorder* recorder);
+
+extern "C" bool isRecording(Recorder* recorder);
+
+extern "C" uint32_t getRecorderChannels(Recorder* recorder);
+
+extern "C" uint32_t getRecorderSampleRate(Recorder* recorder);
+
+extern "C" size_t getRecordedLength(Recorder* recorder);
+
+extern "C" size_t takeRecorded(Recorder* recorder, float* output, size_t capacity);
+
+extern "C" void closeRecorder(AudioContext* context, Recorder* recorder);
+
+extern "C" void pauseEngine(AudioContext* context);
+
+extern "C" void resumeEngine(AudioContext* context);
+
+extern "C" void stopAll(AudioContext* context);
+
+extern "C" void resumeAll(AudioContext* context);
+
+extern "C" bool isEnginePaused(AudioContext* context);
+
+extern "C" void* createGroup();
+
+extern "C" void setGroupVolume(void* group, float volume);
+
+extern "C" float getGroupVolume(void* group);
+
+extern "C" void setGroupMuted(void* group, bool muted);
+
+extern "C" bool isGroupMuted(void* group);
+
+extern "C" void setGroupDuck(void* group, void* trigger, float gain, uint64_t attackMicros, uint64_t releaseMicros);
+
+extern "C" void setMasterVolume(AudioContext* context, float volume);
+
+extern "C" float getMasterVolume(AudioContext* context);
+
+extern "C" void releaseGroup(void* group);
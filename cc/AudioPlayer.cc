@@ -2,6 +2,7 @@
 #include "stb_vorbis.c"
 #define MINIAUDIO_IMPLEMENTATION
 #include "AudioPlayer.h"
+#include "AudioInterface.h"
 #undef STB_VORBIS_HEADER_ONLY
 #include "stb_vorbis.c"
 
@@ -16,26 +17,801 @@ void data_callback(ma_device* device, void* output, const void*, ma_uint32 frame
 	if(&clip->decoder == NULL){
 			return;
 	}
-	ma_uint64 framesRead = ma_decoder_read_pcm_frames(&clip->decoder, output, framesToRead);
-	if(framesRead < framesToRead){
-		std::lock_guard<std::mutex> lock(clip->mtx);
-		float oldVolume = device->masterVolumeFactor;
-		device->masterVolumeFactor = 0;
-		ma_decoder_seek_to_pcm_frame(&clip->decoder, 0);
-		resetDevice(device, clip, oldVolume);
+	//the end was already reached and the device is being stopped
+	if(clip->ending){
+		return;
 	}
+	applyPendingSeek(clip);
+	//primed devices keep running and output silence until played
+	if(clip->primed && !clip->started){
+		return;
+	}
+	ma_uint64 loops = clip->loops;
+	ma_uint64 framesRead = clip->tempo == 1 ? readFrames(clip, output, framesToRead) : stretchFrames(clip, output, framesToRead);
+	reportProgress(clip, framesRead);
+	reportLoops(clip, loops);
+	float peak = processBlock(clip, output, framesRead);
+	if(framesRead < framesToRead || detectSilence(clip, peak, framesRead)){
+		endClip(device, clip);
+	}
+	publishCursor(clip);
 }
 
-void resetDevice(ma_device* device, SoundClip* clip, float const& oldVolume) {
-	std::thread t{[device, clip, oldVolume](){
-		std::lock_guard<std::mutex> lock(clip->mtx);
-		ma_device_stop(device);
-		ma_decoder_seek_to_pcm_frame(&clip->decoder, 0);
-		device->masterVolumeFactor = oldVolume;
-		endCallback(clip->outer);
+//every stop requested by the library is flagged, any other stop means the device went away
+void stop_callback(ma_device* device) {
+	SoundClip* clip = (SoundClip*)device->pUserData;
+	if(clip->stopping || clip->ending){
+		return;
+	}
+	recoverClip(clip);
+}
+
+//the device can not be reinitialized from its own callback so the clip is moved on another thread
+//it reopens the default device which is recorded so the clip reports where it plays now
+void recoverClip(SoundClip* clip) {
+	spawnClipTask(clip, [clip]() -> void* {
+		float volume = clip->device.masterVolumeFactor;
+		clip->deviceConfig.playback.pDeviceID = NULL;
+		AudioDevice device{ma_device_id{}, nullptr};
+		if(tryGetDefaultAudioDevice(clip->context, &device)){
+			clip->audioDevice = device;
+		}
+		clip->stopping = true;
+		ma_device_uninit(&clip->device);
+		//a device that failed to initialize is left zeroed which every later call treats as uninitialized
+		ma_result result = ma_device_init(clip->context->context, &clip->deviceConfig, &clip->device);
+		clip->stopping = false;
+		if(result == MA_SUCCESS){
+			clip->device.masterVolumeFactor = volume;
+			ma_device_start(&clip->device);
+		}
+		return clip->outer;
+	}, deviceLostCallback);
+}
+
+//the task runs under the lock of the clip unless it was released and returns the outer to call back or null
+//the callback runs without the lock held since it may control the clip
+void spawnClipTask(SoundClip* clip, std::function<void*()> task, void (*callback)(void*)) {
+	clip->refs++;
+	std::thread t{[clip, task, callback](){
+		void* outer = nullptr;
+		{
+			std::lock_guard<std::mutex> lock(clip->mtx);
+			if(!clip->removed){
+				outer = task();
+			}
+			if(outer != nullptr){
+				clip->callbacks++;
+			}
+		}
+		if(outer != nullptr){
+			runningCallback = clip;
+			callback(outer);
+			runningCallback = nullptr;
+			std::lock_guard<std::mutex> lock(clip->mtx);
+			clip->callbacks--;
+			clip->callbacksDone.notify_all();
+		}
+		releaseClipRef(clip);
 	}};
 	t.detach();
 }
 
+void releaseClipRef(SoundClip* clip) {
+	if(--clip->refs == 0){
+		delete clip;
+	}
+}
+
+void capture_callback(ma_device* device, void*, const void* input, ma_uint32 frameCount) {
+	Recorder* recorder = (Recorder*)device->pUserData;
+	const float* samples = (const float*)input;
+	std::lock_guard<std::mutex> lock(recorder->mtx);
+	recorder->samples.insert(recorder->samples.end(), samples, samples + frameCount*device->capture.channels);
+}
+
+void endClip(ma_device* device, SoundClip* clip) {
+	finishFade(clip);
+	endBlockTap(clip);
+	clip->stretchReset = true;
+	clip->silentFrames = 0;
+	clip->peak = 0;
+	clip->preFaderPeak = 0;
+	clip->rms = 0;
+	if(clip->context->offline){
+		//offline clips are pumped by advance which fires the end callback itself
+		clip->started = false;
+		ma_decoder_seek_to_pcm_frame(&clip->decoder, clip->rangeStart);
+		clip->ended = true;
+		clip->pendingEnd = true;
+		return;
+	}
+	//the device is running so the decoder belongs to this thread and no lock is needed
+	clip->ending = true;
+	float oldVolume = device->masterVolumeFactor;
+	device->masterVolumeFactor = 0;
+	ma_decoder_seek_to_pcm_frame(&clip->decoder, clip->rangeStart);
+	resetDevice(device, clip, oldVolume);
+}
+
+void resetDevice(ma_device* device, SoundClip* clip, float const& oldVolume) {
+	spawnClipTask(clip, [device, clip, oldVolume]() -> void* {
+		stopClip(clip);
+		device->masterVolumeFactor = oldVolume;
+		clip->ending = false;
+		clip->ended = true;
+		//setOuter delivers the end once the handle exists
+		clip->pendingEnd = clip->outer == nullptr;
+		return clip->outer;
+	}, endCallback);
+}
+
+ma_result startClip(SoundClip* clip) {
+	if(clip->context->offline || clip->primed){
+		clip->started = true;
+		return MA_SUCCESS;
+	}
+	return ma_device_start(&clip->device);
+}
+
+ma_result stopClip(SoundClip* clip) {
+	if(clip->context->offline || clip->primed){
+		clip->started = false;
+		return MA_SUCCESS;
+	}
+	clip->stopping = true;
+	ma_result result = ma_device_stop(&clip->device);
+	clip->stopping = false;
+	return result;
+}
+
+bool isClipStarted(SoundClip* clip) {
+	if(clip->context->offline || clip->primed){
+		return clip->started;
+	}
+	return ma_device_is_started(&clip->device);
+}
+
+//must be called with the lock of the clip held so the device is not started in between
+//a seek on a running device is only checked against isSeekable since it is applied later
+bool seekClip(SoundClip* clip, ma_uint64 frame) {
+	if(!clip->context->offline && (clip->primed || ma_device_is_started(&clip->device))){
+		if(clip->decoder.onSeekToPCMFrame == nullptr || clip->decoder.onSeek == nullptr){
+			return false;
+		}
+		clip->pendingSeek = frame;
+		return true;
+	}
+	clip->pendingSeek = NO_SEEK;
+	if(ma_decoder_seek_to_pcm_frame(&clip->decoder, frame) != MA_SUCCESS){
+		return false;
+	}
+	publishCursor(clip);
+	return true;
+}
+
+void applyPendingSeek(SoundClip* clip) {
+	ma_uint64 frame = clip->pendingSeek.exchange(NO_SEEK);
+	if(frame == NO_SEEK){
+		return;
+	}
+	ma_decoder_seek_to_pcm_frame(&clip->decoder, frame);
+	clip->stretchReset = true;
+	publishCursor(clip);
+}
+
+void publishCursor(SoundClip* clip) {
+	ma_uint64 cursor = 0;
+	ma_decoder_get_cursor_in_pcm_frames(&clip->decoder, &cursor);
+	clip->cursor = cursor;
+}
+
+//a seek that is still waiting for the audio thread is reported as if it happened
+ma_uint64 clipCursor(SoundClip* clip) {
+	ma_uint64 frame = clip->pendingSeek;
+	return frame != NO_SEEK ? frame : clip->cursor.load();
+}
+
+//the decoding backend is only recorded through its callbacks
+int detectCodec(ma_decoder* decoder) {
+	if(decoder->onUninit == ma_decoder_internal_on_uninit__wav) {
+		return 1;
+	}
+	if(decoder->onUninit == ma_decoder_internal_on_uninit__flac) {
+		return 2;
+	}
+	if(decoder->onUninit == ma_decoder_internal_on_uninit__mp3) {
+		return 3;
+	}
+	if(decoder->onUninit == ma_decoder_internal_on_uninit__vorbis) {
+		return 4;
+	}
+	return 0;
+}
+
+//measured in the time of the file like positions so it does not change with the playback rate
+uint64_t clipDuration(SoundClip* clip) {
+	uint64_t sampleRate{clip->decoder.outputSampleRate};
+	uint64_t end{clipEnd(clip)};
+	if(sampleRate == 0 || end < clip->rangeStart){
+		return 0;
+	}
+	return (end - clip->rangeStart)*1000/sampleRate;
+}
+
+//the cursor counts frames handed to the device so it runs ahead of the speaker by the device buffer
+uint64_t clipPosition(SoundClip* clip) {
+	ma_uint64 cursor = clipCursor(clip);
+	if(clip->decoder.outputSampleRate == 0 || cursor < clip->rangeStart){
+		return 0;
+	}
+	return (cursor - clip->rangeStart)*1000000/clip->decoder.outputSampleRate;
+}
+
+//0 stopped, 1 paused, 2 playing
+int clipState(SoundClip* clip) {
+	if(clip->ending || clip->ended){
+		return 0;
+	}
+	if(isClipStarted(clip)){
+		return 2;
+	}
+	return clipCursor(clip) == clip->rangeStart ? 0 : 1;
+}
+
+//the device volume is held at one while fading so the ramp is what is heard
+float clipVolume(SoundClip* clip) {
+	if(clip->fading){
+		return clip->fadeVolume;
+	}
+	return clip->device.masterVolumeFactor;
+}
+
+float clipSpeed(SoundClip* clip) {
+	if(clip->decoder.outputSampleRate == 0){
+		return 1;
+	}
+	return (float)clip->device.sampleRate / clip->decoder.outputSampleRate;
+}
+
+//last frame of the range, zero if the length of the file is unknown
+ma_uint64 clipEnd(SoundClip* clip) {
+	ma_uint64 length = clip->length;
+	if(clip->rangeEnd != 0 && (length == 0 || clip->rangeEnd < length)){
+		return clip->rangeEnd;
+	}
+	return length;
+}
+
+//looping clips and loop regions wrap around inside the block so there is no gap at the seam
+ma_uint64 readFrames(SoundClip* clip, void* output, ma_uint64 frameCount) {
+	ma_uint32 frameSize = ma_get_bytes_per_frame(clip->decoder.outputFormat, clip->decoder.outputChannels);
+	ma_uint64 framesRead = 0;
+	bool wrapped = false;
+	while(framesRead < frameCount){
+		ma_uint64 toRead = frameCount - framesRead;
+		ma_uint64 loopEnd = clip->loopEnd;
+		bool rangeEnded = false;
+		if(loopEnd != 0 || clip->rangeEnd != 0){
+			ma_uint64 cursor = 0;
+			ma_decoder_get_cursor_in_pcm_frames(&clip->decoder, &cursor);
+			if(loopEnd != 0 && cursor >= loopEnd){
+				ma_decoder_seek_to_pcm_frame(&clip->decoder, clip->loopStart);
+				clip->loops++;
+				continue;
+			}
+			if(loopEnd != 0){
+				toRead = std::min(toRead, loopEnd - cursor);
+			}
+			//the end of the range is treated like the end of the file, loop regions are clamped inside it
+			if(loopEnd == 0 && clip->rangeEnd != 0){
+				ma_uint64 left = clip->rangeEnd > cursor ? clip->rangeEnd - cursor : 0;
+				rangeEnded = left <= toRead;
+				toRead = std::min(toRead, left);
+			}
+		}
+		ma_uint64 read = ma_decoder_read_pcm_frames(&clip->decoder, (ma_uint8*)output + framesRead*frameSize, toRead);
+		framesRead += read;
+		//a wrap only counts once the start plays again, a clip with nothing to loop does not loop
+		if(wrapped && read > 0){
+			clip->loops++;
+		}
+		if(read == toRead && !rangeEnded){
+			wrapped = false;
+			continue;
+		}
+		//the end of the file was reached, an empty read right after wrapping means there is nothing to loop
+		if(!clip->looping || (wrapped && read == 0)){
+			break;
+		}
+		ma_decoder_seek_to_pcm_frame(&clip->decoder, clip->rangeStart);
+		wrapped = true;
+	}
+	return framesRead;
+}
+
+//wsola time stretch, windows of the source are overlapped at a fixed hop and each one is picked
+//from around its ideal position where it lines up best with the previous one so the pitch is kept
+ma_uint64 stretchFrames(SoundClip* clip, void* output, ma_uint64 frameCount) {
+	Stretcher& stretcher = clip->stretcher;
+	if(clip->stretchReset.exchange(false)){
+		clearStretcher(stretcher);
+	}
+	ma_uint32 channels = clip->decoder.outputChannels;
+	//40ms windows are long enough for low voices and short enough to not smear transients
+	ma_uint64 window = std::max<ma_uint64>(clip->decoder.outputSampleRate / 25, 64) & ~(ma_uint64)1;
+	double tempo = clip->tempo;
+
+	std::vector<float>& samples = clip->scratch;
+	samples.resize(frameCount*channels);
+	ma_uint64 written = 0;
+	while(written < frameCount){
+		ma_uint64 pending = stretcher.output.size()/channels - stretcher.outputFrame;
+		if(pending == 0){
+			stretcher.output.clear();
+			stretcher.outputFrame = 0;
+			if(!stretchWindow(clip, window, tempo)){
+				break;
+			}
+			continue;
+		}
+		ma_uint64 count = std::min(pending, frameCount - written);
+		std::copy_n(stretcher.output.begin() + stretcher.outputFrame*channels, count*channels, samples.begin() + written*channels);
+		stretcher.outputFrame += count;
+		written += count;
+	}
+	ma_pcm_convert(output, clip->decoder.outputFormat, samples.data(), ma_format_f32, written*channels, ma_dither_mode_none);
+	return written;
+}
+
+//appends one hop of stretched frames to the output, false once the source is used up
+bool stretchWindow(SoundClip* clip, ma_uint64 window, double tempo) {
+	Stretcher& stretcher = clip->stretcher;
+	ma_uint32 channels = clip->decoder.outputChannels;
+	ma_uint64 hop = window/2;
+	ma_uint64 tolerance = window/4;
+	if(stretcher.drained){
+		return false;
+	}
+
+	//the source is decoded until the furthest window that can be picked is available
+	ma_uint64 needed = stretcher.primed ? (ma_uint64)stretcher.position + tolerance + window : window;
+	ma_uint32 frameSize = ma_get_bytes_per_frame(clip->decoder.outputFormat, channels);
+	while(stretcher.input.size()/channels < needed && !stretcher.ended){
+		ma_uint64 toRead = needed - stretcher.input.size()/channels;
+		stretcher.raw.resize(toRead*frameSize);
+		ma_uint64 read = readFrames(clip, stretcher.raw.data(), toRead);
+		size_t offset = stretcher.input.size();
+		stretcher.input.resize(offset + read*channels);
+		ma_pcm_convert(stretcher.input.data() + offset, ma_format_f32, stretcher.raw.data(), clip->decoder.outputFormat, read*channels, ma_dither_mode_none);
+		if(read < toRead){
+			stretcher.ended = true;
+		}
+	}
+	ma_uint64 available = stretcher.input.size()/channels;
+	const float* input = stretcher.input.data();
+
+	//the first hop has nothing to overlap with
+	if(!stretcher.primed){
+		ma_uint64 count = std::min(hop, available);
+		if(count == 0){
+			stretcher.drained = true;
+			return false;
+		}
+		stretcher.output.assign(input, input + count*channels);
+		stretcher.previous = 0;
+		stretcher.position = tempo*hop;
+		stretcher.primed = true;
+		return true;
+	}
+
+	//at the end the rest of the last window is played as it is
+	ma_uint64 ideal = (ma_uint64)stretcher.position;
+	ma_uint64 tail = stretcher.previous + hop;
+	if(stretcher.ended && ideal + hop > available){
+		ma_uint64 end = std::min(stretcher.previous + window, available);
+		stretcher.drained = true;
+		if(end <= tail){
+			return false;
+		}
+		stretcher.output.assign(input + tail*channels, input + end*channels);
+		return true;
+	}
+
+	//the candidate that correlates best with the natural continuation of the previous window wins
+	ma_uint64 first = ideal > tolerance ? ideal - tolerance : 0;
+	ma_uint64 last = std::min(ideal + tolerance, available - std::min(available, hop));
+	ma_uint64 best = std::min(ideal, last);
+	double bestScore = -2;
+	for(ma_uint64 candidate = first; candidate <= last; candidate++){
+		double correlation = 0;
+		double energy = 0;
+		//every fourth frame is enough to line up the waveforms and keeps the search cheap
+		for(ma_uint64 frame = 0; frame < hop && tail + frame < available; frame += 4){
+			for(ma_uint32 channel = 0; channel < channels; channel++){
+				float target = input[(tail + frame)*channels + channel];
+				float sample = input[(candidate + frame)*channels + channel];
+				correlation += target*sample;
+				energy += sample*sample;
+			}
+		}
+		double score = energy > 0 ? correlation / std::sqrt(energy) : 0;
+		if(score > bestScore){
+			bestScore = score;
+			best = candidate;
+		}
+	}
+
+	//hann windows at half overlap sum to one so the crossfade keeps the level
+	stretcher.output.resize(hop*channels);
+	const double pi = 3.14159265358979323846;
+	for(ma_uint64 frame = 0; frame < hop; frame++){
+		float fadeIn = (float)(0.5 - 0.5*std::cos(pi*frame/hop));
+		for(ma_uint32 channel = 0; channel < channels; channel++){
+			float from = tail + frame < available ? input[(tail + frame)*channels + channel] : 0;
+			float to = best + frame < available ? input[(best + frame)*channels + channel] : 0;
+			stretcher.output[frame*channels + channel] = from*(1 - fadeIn) + to*fadeIn;
+		}
+	}
+	stretcher.previous = best;
+	stretcher.position += tempo*hop;
+
+	//frames before the earliest window that can still be picked are dropped
+	ma_uint64 keep = std::min(stretcher.previous, (ma_uint64)stretcher.position > tolerance ? (ma_uint64)stretcher.position - tolerance : 0);
+	if(keep > 0){
+		stretcher.input.erase(stretcher.input.begin(), stretcher.input.begin() + keep*channels);
+		stretcher.previous -= keep;
+		stretcher.position -= keep;
+	}
+	return true;
+}
+
+void clearStretcher(Stretcher& stretcher) {
+	stretcher.input.clear();
+	stretcher.output.clear();
+	stretcher.outputFrame = 0;
+	stretcher.previous = 0;
+	stretcher.position = 0;
+	stretcher.primed = false;
+	stretcher.ended = false;
+	stretcher.drained = false;
+}
+
+//every stage works on one float copy of the block which is converted back at the end
+//returns the peak before any processing so silence detection does not follow the volume
+float processBlock(SoundClip* clip, void* frames, ma_uint64 frameCount) {
+	if(frameCount == 0){
+		clip->preFaderPeak = 0;
+		clip->peak = 0;
+		clip->rms = 0;
+		return 0;
+	}
+	ma_uint32 channels = clip->decoder.outputChannels;
+	ma_format format = clip->decoder.outputFormat;
+	ma_uint64 sampleCount = frameCount * channels;
+	std::vector<float>& samples = clip->scratch;
+	samples.resize(sampleCount);
+	ma_pcm_convert(samples.data(), ma_format_f32, frames, format, sampleCount, ma_dither_mode_none);
+
+	float preFaderPeak = samplePeak(samples.data(), sampleCount);
+	clip->preFaderPeak = preFaderPeak;
+	void (*processor)(void*, float*, ma_uint32, ma_uint32) = clip->context->processor;
+	if(processor != nullptr && !clip->bypassProcessor){
+		processor(clip->context->processorUser, samples.data(), (ma_uint32)frameCount, channels);
+	}
+	if(clip->fading){
+		applyFade(clip, samples.data(), frameCount, channels);
+	}
+	float pan = clip->pan;
+	if(pan != 0 && channels == 2){
+		applyPan(pan, samples.data(), frameCount, channels);
+	}
+	if(clip->spatial){
+		applySpatial(clip, samples.data(), frameCount, channels);
+	}
+	float gain = groupGain(clip, samples.data(), sampleCount)*clip->context->state->masterVolume;
+	if(gain != 1){
+		for(ma_uint64 i = 0; i < sampleCount; i++) {
+			samples[i] *= gain;
+		}
+	}
+	pushBlock(clip, samples.data(), sampleCount);
+	clip->peak = samplePeak(samples.data(), sampleCount);
+	clip->rms = sampleRms(samples.data(), sampleCount);
+
+	ma_pcm_convert(frames, format, samples.data(), ma_format_f32, sampleCount, ma_dither_mode_none);
+	return preFaderPeak;
+}
+
+float samplePeak(const float* samples, ma_uint64 sampleCount) {
+	float peak = 0;
+	for(ma_uint64 i = 0; i < sampleCount; i++) {
+		peak = std::max(peak, std::abs(samples[i]));
+	}
+	return peak;
+}
+
+float sampleRms(const float* samples, ma_uint64 sampleCount) {
+	if(sampleCount == 0){
+		return 0;
+	}
+	double sum = 0;
+	for(ma_uint64 i = 0; i < sampleCount; i++) {
+		sum += samples[i]*samples[i];
+	}
+	return (float)std::sqrt(sum/sampleCount);
+}
+
+//blocks that are heard through the group mark it active for the groups ducked under it
+float groupGain(SoundClip* clip, float* samples, ma_uint64 sampleCount) {
+	std::shared_ptr<SoundGroup> group = std::atomic_load(&clip->group);
+	if(!group){
+		return 1;
+	}
+	int64_t now = contextMicros(clip->context);
+	float gain = group->muted ? 0 : group->volume * duckGain(group.get(), now);
+	if(gain*samplePeak(samples, sampleCount) > DUCK_THRESHOLD){
+		group->activity->lastActive = now;
+	}
+	return gain;
+}
+
+//every clip of the group moves the shared depth by the time since any of them last did
+float duckGain(SoundGroup* group, int64_t now) {
+	std::shared_ptr<DuckTrigger> trigger = std::atomic_load(&group->trigger);
+	if(!trigger){
+		return 1;
+	}
+	int64_t updated = group->duckUpdated.exchange(now);
+	int64_t elapsed = updated == std::numeric_limits<int64_t>::min() ? 0 : std::max<int64_t>(now - updated, 0);
+	bool active = now - trigger->lastActive < DUCK_HOLD;
+	int64_t time = active ? group->duckAttack : group->duckRelease;
+	float step = time <= 0 ? 1 : (float)elapsed / time;
+	float depth = group->duckDepth;
+	depth = active ? std::min(depth + step, 1.0f) : std::max(depth - step, 0.0f);
+	group->duckDepth = depth;
+	return 1 + (group->duckGain - 1)*depth;
+}
+
+//offline contexts run on the clock moved by advance instead of the wall clock
+int64_t contextMicros(AudioContext* context) {
+	if(context->offline){
+		return context->state->clock;
+	}
+	return std::chrono::duration_cast<std::chrono::microseconds>(std::chrono::steady_clock::now().time_since_epoch()).count();
+}
+
+//the device volume is held at one while fading so the ramp carries the whole volume
+void applyFade(SoundClip* clip, float* samples, ma_uint64 frameCount, ma_uint32 channels) {
+	float volume = clip->fadeVolume;
+	float from = clip->fadeFrom;
+	float target = clip->fadeTarget;
+	ma_uint64 elapsed = clip->fadeElapsed;
+	ma_uint64 frames = clip->fadeFrames;
+	int curve = clip->fadeCurve;
+	for(ma_uint64 frame = 0; frame < frameCount; frame++) {
+		elapsed = std::min(elapsed + 1, frames);
+		volume = fadeShape(curve, from, target, (float)elapsed / frames);
+		for(ma_uint32 channel = 0; channel < channels; channel++) {
+			samples[frame*channels + channel] *= volume;
+		}
+	}
+	clip->fadeVolume = volume;
+	clip->fadeElapsed = elapsed;
+	if(elapsed < frames){
+		return;
+	}
+	if(!clip->fadeStop){
+		finishFade(clip);
+		return;
+	}
+	clip->fading = false;
+	float restore = clip->fadeRestore;
+	if(clip->context->offline || clip->primed){
+		clip->started = false;
+		clip->device.masterVolumeFactor = restore;
+		reportFadeStop(clip);
+		return;
+	}
+	//the device can not be stopped from its own callback so like endClip it stays silent until it is
+	clip->device.masterVolumeFactor = 0;
+	spawnClipTask(clip, [clip, restore]() -> void* {
+		stopClip(clip);
+		clip->device.masterVolumeFactor = restore;
+		return clip->outer;
+	}, fadeStopCallback);
+}
+
+void finishFade(SoundClip* clip) {
+	if(!clip->fading.exchange(false)){
+		return;
+	}
+	clip->device.masterVolumeFactor = clip->fadeStop ? clip->fadeRestore.load() : clip->fadeTarget.load();
+}
+
+//a cancelled fade is held at the volume it reached and a fade out that would stop the clip goes back to the volume before it
+void cancelFade(SoundClip* clip) {
+	if(!clip->fading.exchange(false)){
+		return;
+	}
+	if(!clip->fadeStop){
+		clip->device.masterVolumeFactor = clip->fadeVolume;
+		return;
+	}
+	clip->device.masterVolumeFactor = clip->fadeRestore;
+	reportFadeStop(clip);
+}
+
+//a fade out ends when it stops the clip or is cancelled, either way the outer may release a clip waiting for it
+//offline clips are pumped by advance which reports it itself
+void reportFadeStop(SoundClip* clip) {
+	if(clip->context->offline){
+		clip->pendingFadeStop = true;
+		return;
+	}
+	spawnClipTask(clip, [clip]() -> void* {
+		return clip->outer;
+	}, fadeStopCallback);
+}
+
+//0 linear, 1 exponential, 2 logarithmic and 3 sine
+//curves are shaped from the quiet end so a fade in and a fade out with the same curve mirror each other
+float fadeShape(int curve, float from, float to, float progress) {
+	if(curve == 0){
+		return from + (to - from)*progress;
+	}
+	float quiet = std::min(from, to);
+	float loud = std::max(from, to);
+	float loudness = to > from ? progress : 1 - progress;
+	float shaped;
+	if(curve == 1){
+		shaped = loudness*loudness*loudness;
+	} else if(curve == 2){
+		shaped = 1 - (1 - loudness)*(1 - loudness)*(1 - loudness);
+	} else {
+		//the squares of a sine fade in and its mirrored fade out always sum to one
+		shaped = std::sin(loudness*MA_PI/2);
+	}
+	return quiet + (loud - quiet)*shaped;
+}
+
+//balance panning attenuates the opposite side and leaves the panned side untouched
+void applyPan(float pan, float* samples, ma_uint64 frameCount, ma_uint32 channels) {
+	float left = pan > 0 ? 1 - pan : 1;
+	float right = pan < 0 ? 1 + pan : 1;
+	for(ma_uint64 frame = 0; frame < frameCount; frame++) {
+		samples[frame*channels] *= left;
+		samples[frame*channels + 1] *= right;
+	}
+}
+
+//the gains of the previous block are ramped to the new ones so moving sources do not click
+void applySpatial(SoundClip* clip, float* samples, ma_uint64 frameCount, ma_uint32 channels) {
+	Listener& listener = clip->context->state->listener;
+	float offset[3];
+	float distance = 0;
+	for(int axis = 0; axis < 3; axis++) {
+		offset[axis] = clip->position[axis] - listener.position[axis];
+		distance += offset[axis]*offset[axis];
+	}
+	distance = std::sqrt(distance);
+	float gain = distanceGain(clip->attenuation, distance);
+
+	//with up being +y the right of the listener is the forward direction turned around y
+	float pan = 0;
+	float rightX = -listener.direction[2];
+	float rightZ = listener.direction[0];
+	float rightLength = std::sqrt(rightX*rightX + rightZ*rightZ);
+	if(distance > 0 && rightLength > 0){
+		pan = (offset[0]*rightX + offset[2]*rightZ) / (distance*rightLength);
+	}
+	float left = gain;
+	float right = gain;
+	if(channels == 2){
+		left *= pan > 0 ? 1 - pan : 1;
+		right *= pan < 0 ? 1 + pan : 1;
+	}
+
+	float fromLeft = clip->spatialLeft;
+	float fromRight = clip->spatialRight;
+	for(ma_uint64 frame = 0; frame < frameCount; frame++) {
+		float t = (float)(frame + 1) / frameCount;
+		float leftGain = fromLeft + (left - fromLeft)*t;
+		float rightGain = fromRight + (right - fromRight)*t;
+		for(ma_uint32 channel = 0; channel < channels; channel++) {
+			samples[frame*channels + channel] *= channel == 1 ? rightGain : leftGain;
+		}
+	}
+	clip->spatialLeft = left;
+	clip->spatialRight = right;
+}
+
+//0 none, 1 linear and 2 exponential, sources closer than the reference distance are not attenuated
+const float REFERENCE_DISTANCE = 1;
+const float MAX_DISTANCE = 100;
+
+float distanceGain(int model, float distance) {
+	if(distance <= REFERENCE_DISTANCE){
+		return 1;
+	}
+	switch(model) {
+		case 1:
+			return std::max(0.0f, 1 - (distance - REFERENCE_DISTANCE) / (MAX_DISTANCE - REFERENCE_DISTANCE));
+		case 2:
+			return REFERENCE_DISTANCE / distance;
+		default:
+			return 1;
+	}
+}
+
+//the callback only signals the outer handle which does the actual work on its own thread
+void reportProgress(SoundClip* clip, ma_uint64 frameCount) {
+	ma_uint64 interval = clip->progressInterval;
+	if(interval == 0 || clip->outer == nullptr){
+		return;
+	}
+	clip->progressFrames += frameCount;
+	if(clip->progressFrames < interval){
+		return;
+	}
+	clip->progressFrames %= interval;
+	progressCallback(clip->outer);
+}
+
+//offline clips are pumped by advance which reports their loops itself
+void reportLoops(SoundClip* clip, ma_uint64 before) {
+	if(clip->loops == before || clip->outer == nullptr || clip->context->offline){
+		return;
+	}
+	loopCallback(clip->outer);
+}
+
+bool detectSilence(SoundClip* clip, float peak, ma_uint64 frameCount) {
+	float threshold = clip->silenceThreshold;
+	if(threshold < 0 || frameCount == 0){
+		return false;
+	}
+	if(peak > threshold){
+		clip->silentFrames = 0;
+		return false;
+	}
+	clip->silentFrames += frameCount;
+	return clip->silentFrames >= clip->silenceMinFrames;
+}
+
+//a block that does not fit is dropped, the blocks already queued are left for the consumer
+void pushBlock(SoundClip* clip, const float* samples, ma_uint64 sampleCount) {
+	std::shared_ptr<BlockTap> tap = std::atomic_load(&clip->tap);
+	if(!tap){
+		return;
+	}
+	size_t block = tap->writeBlock;
+	size_t sample = tap->writeSample;
+	if(block - tap->readBlock >= MAX_QUEUED_BLOCKS || sampleCount > TAP_SAMPLES - (sample - tap->readSample)){
+		return;
+	}
+	for(ma_uint64 i = 0; i < sampleCount; i++) {
+		tap->samples[(sample + i) % TAP_SAMPLES] = samples[i];
+	}
+	tap->lengths[block % MAX_QUEUED_BLOCKS] = sampleCount;
+	tap->writeSample = sample + sampleCount;
+	tap->writeBlock = block + 1;
+	tap->cv.notify_one();
+}
+
+void endBlockTap(SoundClip* clip) {
+	std::shared_ptr<BlockTap> tap = std::atomic_exchange(&clip->tap, std::shared_ptr<BlockTap>());
+	if(!tap){
+		return;
+	}
+	tap->ended = true;
+	tap->cv.notify_all();
+}
+
 void (*endCallback)(void*) = nullptr;
+void (*progressCallback)(void*) = nullptr;
+void (*loopCallback)(void*) = nullptr;
+void (*fadeStopCallback)(void*) = nullptr;
+void (*deviceLostCallback)(void*) = nullptr;
 std::mutex global{};
+thread_local SoundClip* runningCallback = nullptr;
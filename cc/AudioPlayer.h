@@ -7,9 +7,54 @@
 #include <mutex>
 #include <array>
 #include <iostream>
+#include <vector>
+#include <memory>
+#include <condition_variable>
+#include <algorithm>
+#include <cmath>
+#include <chrono>
+#include <limits>
 
 struct AudioContext;
 
+//captured samples are kept as f32 until they are taken
+struct Recorder {
+	ma_device device;
+	std::mutex mtx;
+	std::vector<float> samples;
+};
+
+//format every clip of a context is converted to, 0 keeps the value of the file or device
+struct EngineConfig {
+	uint32_t sampleRate;
+	uint32_t channels;
+	uint32_t bufferFrames;
+	//index into ma_backend, negative picks the first backend that works
+	int32_t backend;
+	//0 shared, 1 exclusive
+	int32_t shareMode;
+};
+
+//position and facing of the listener that spatial clips are heard from, up is always +y
+struct Listener {
+	std::array<std::atomic<float>, 3> position;
+	std::array<std::atomic<float>, 3> direction;
+};
+
+//mutable state of a context that is owned by the C++ side
+struct ContextState {
+	std::atomic<bool> paused;
+	//applied on top of the volume of every clip and group
+	std::atomic<float> masterVolume;
+	//time in micros of offline contexts which only moves in advance
+	std::atomic<int64_t> clock;
+	//bytes of in memory sources the clips may hold together, zero is no limit
+	std::atomic<size_t> memoryBudget;
+	std::vector<Recorder*> recorders;
+	EngineConfig config;
+	Listener listener;
+};
+
 struct AudioDevice {
 	ma_device_id id;
 	const char* name;
@@ -20,25 +65,270 @@ struct AudioDeviceList {
     AudioDevice* devices;
 };
 
+//blocks are dropped once the consumer falls this far behind so the audio thread never waits
+const size_t MAX_QUEUED_BLOCKS = 64;
+const size_t TAP_SAMPLES = 1 << 18;
+
+//single producer single consumer rings allocated up front so the audio thread never allocates or locks
+//the counters only grow, the audio thread owns the write ones and the consumer the read ones
+struct BlockTap {
+	std::vector<float> samples;
+	std::vector<size_t> lengths;
+	std::atomic<size_t> writeBlock;
+	std::atomic<size_t> writeSample;
+	std::atomic<size_t> readBlock;
+	std::atomic<size_t> readSample;
+	std::atomic<bool> ended;
+	//only the consumer locks this to sleep, the audio thread notifies without it
+	std::mutex mtx;
+	std::condition_variable cv;
+};
+
+//byte source implemented by the caller, user is passed back to both callbacks
+struct ReaderSource {
+	size_t (*read)(void* user, void* buffer, size_t size);
+	bool (*seek)(void* user, int64_t offset, int origin);
+	void* user;
+};
+
+//time stretch state that is only touched by the audio callback
+//input holds decoded f32 frames, position is where the next window is expected in it
+struct Stretcher {
+	std::vector<float> input;
+	std::vector<float> output;
+	std::vector<ma_uint8> raw;
+	ma_uint64 outputFrame;
+	ma_uint64 previous;
+	double position;
+	bool primed;
+	bool ended;
+	bool drained;
+};
+
+//a trigger counts as active for this long after its last audible block
+const int64_t DUCK_HOLD = 50000;
+//peak below which a block of a trigger is treated as silence, about -60 dB
+const float DUCK_THRESHOLD = 0.001f;
+
+//kept apart from the group so two groups can duck under each other without owning each other
+struct DuckTrigger {
+	//time in micros of the last audible block of any clip in the group
+	std::atomic<int64_t> lastActive;
+};
+
+//gain shared by every clip routed to the group
+struct SoundGroup {
+	std::atomic<float> volume;
+	std::atomic<bool> muted;
+	std::shared_ptr<DuckTrigger> activity;
+	//the group is lowered to duckGain while the trigger is active, depth ramps between 0 and 1
+	std::shared_ptr<DuckTrigger> trigger;
+	std::atomic<float> duckGain;
+	std::atomic<int64_t> duckAttack;
+	std::atomic<int64_t> duckRelease;
+	std::atomic<float> duckDepth;
+	std::atomic<int64_t> duckUpdated;
+};
+
 struct SoundClip {
 	ma_device device;
 	ma_decoder decoder;
 	ma_device_config deviceConfig;
 	size_t id;
 	std::mutex mtx;
-	AudioDevice* audioDevice;
+	//copied since the device config points at its id for as long as the clip lives
+	AudioDevice audioDevice;
 	AudioContext* context;
 	void* outer;
+	std::atomic<bool> started;
+	std::atomic<bool> ending;
+	std::atomic<bool> stopping;
+	std::atomic<bool> primed;
+	std::atomic<bool> ended;
+	std::atomic<bool> replayOnPlay;
+	std::atomic<bool> looping;
+	std::atomic<ma_uint64> loopStart;
+	std::atomic<ma_uint64> loopEnd;
+	//wraps of the loop the outer has not taken yet
+	std::atomic<ma_uint64> loops;
+	//the clip plays from rangeStart to rangeEnd, an end of zero is the end of the file
+	ma_uint64 rangeStart;
+	ma_uint64 rangeEnd;
+	//read once at load since some decoders scan the whole stream to find it, zero if unknown
+	ma_uint64 length;
+	//tells an empty file apart from one whose decoder can not report a length
+	bool lengthKnown;
+	//the audio thread reads the decoder without a lock so a running clip is sought from its callback
+	std::atomic<ma_uint64> pendingSeek;
+	//cursor of the decoder after the last block, read by other threads instead of the decoder
+	std::atomic<ma_uint64> cursor;
+	std::atomic<float> silenceThreshold;
+	std::atomic<ma_uint64> silenceMinFrames;
+	std::atomic<ma_uint64> silentFrames;
+	std::atomic<float> peak;
+	std::atomic<float> preFaderPeak;
+	std::atomic<float> rms;
+	std::atomic<bool> resumeWithEngine;
+	std::atomic<bool> resumeWithAll;
+	std::atomic<bool> fading;
+	std::atomic<float> fadeVolume;
+	std::atomic<float> fadeFrom;
+	std::atomic<float> fadeTarget;
+	//the elapsed part of the fade is mapped to a volume by the curve
+	std::atomic<ma_uint64> fadeElapsed;
+	std::atomic<ma_uint64> fadeFrames;
+	std::atomic<int> fadeCurve;
+	std::atomic<float> fadeRestore;
+	std::atomic<bool> fadeStop;
+	std::atomic<float> pan;
+	std::atomic<float> tempo;
+	//the clip skips the custom processor of the context
+	std::atomic<bool> bypassProcessor;
+	//spatial clips are attenuated and panned by their position relative to the listener
+	std::atomic<bool> spatial;
+	std::array<std::atomic<float>, 3> position;
+	std::atomic<int> attenuation;
+	float spatialLeft;
+	float spatialRight;
+	//set whenever the decoder jumps so the stretcher drops what it buffered
+	std::atomic<bool> stretchReset;
+	Stretcher stretcher;
+	std::atomic<ma_uint64> progressInterval;
+	ma_uint64 progressFrames;
+	//an end that has not been delivered yet, either because advance has not run or the outer is not set
+	bool pendingEnd;
+	//a fade out of an offline clip that ended and has not been reported by advance yet
+	std::atomic<bool> pendingFadeStop;
+	int codec;
+	ReaderSource reader;
+	//in memory source of the clip, clips sharing a buffer count it once against the budget
+	const void* memoryData;
+	size_t memorySize;
+	std::shared_ptr<BlockTap> tap;
+	std::shared_ptr<SoundGroup> group;
+	std::vector<float> scratch;
+	//the context and every detached thread hold a reference, the last one to let go deletes the clip
+	std::atomic<int> refs;
+	//set once the clip is released, threads that still hold a reference skip their work
+	bool removed;
+	//callbacks running on detached threads, releasing the clip waits for them so the outer stays valid
+	int callbacks;
+	std::condition_variable callbacksDone;
+};
+
+//initial settings applied by load before the clip becomes visible
+struct ClipConfig {
+	float volume;
+	bool matchDeviceRate;
+	bool looping;
+	std::shared_ptr<SoundGroup>* group;
+	uint64_t rangeStartMicros;
+	uint64_t rangeEndMicros;
+};
+
+//state is 0 stopped, 1 paused and 2 playing like getState
+struct ClipSnapshot {
+	float volume;
+	uint64_t duration;
+	int state;
+	float pan;
+	float speed;
+	bool looping;
+	uint64_t position;
+};
+
+struct ContextUsage {
+	size_t clips;
+	size_t playing;
+	size_t memory;
+	size_t budget;
+};
+
+//format a device runs at when it is opened with its native settings
+struct DeviceFormat {
+	uint32_t sampleRate;
+	uint32_t channels;
+};
+
+//a whole file decoded to interleaved f32, buffer owns the samples
+struct DecodedClip {
+	uint32_t sampleRate;
+	uint32_t channels;
+	const float* samples;
+	size_t length;
+	void* buffer;
+};
+
+//native properties of the file before any conversion
+struct ClipInfo {
+	uint32_t sampleRate;
+	uint32_t channels;
+	int format;
 };
 
 struct AudioContext {
 	ma_context* context;
 	std::unordered_map<size_t, SoundClip*>* soundClips;
 	bool result;
+	bool offline;
 	std::mutex* mtx;
+	void (*processor)(void*, float*, ma_uint32, ma_uint32);
+	void* processorUser;
+	ContextState* state;
 };
 
+//pendingSeek when no seek is waiting for the audio thread
+const ma_uint64 NO_SEEK = ~(ma_uint64)0;
+
 extern std::mutex global;
 extern void (*endCallback)(void*);
+extern void (*progressCallback)(void*);
+extern void (*loopCallback)(void*);
+extern void (*fadeStopCallback)(void*);
+extern void (*deviceLostCallback)(void*);
+extern thread_local SoundClip* runningCallback;
 void data_callback(ma_device* device, void* output, const void* input, ma_uint32 frameCount);
+void stop_callback(ma_device* device);
+void recoverClip(SoundClip* clip);
+void spawnClipTask(SoundClip* clip, std::function<void*()> task, void (*callback)(void*));
+void releaseClipRef(SoundClip* clip);
+void capture_callback(ma_device* device, void* output, const void* input, ma_uint32 frameCount);
+void endClip(ma_device* device, SoundClip* clip);
 void resetDevice(ma_device* device, SoundClip* clip, float const& oldVolume);
+ma_result startClip(SoundClip* clip);
+ma_result stopClip(SoundClip* clip);
+bool isClipStarted(SoundClip* clip);
+bool seekClip(SoundClip* clip, ma_uint64 frame);
+void applyPendingSeek(SoundClip* clip);
+void publishCursor(SoundClip* clip);
+ma_uint64 clipCursor(SoundClip* clip);
+int detectCodec(ma_decoder* decoder);
+uint64_t clipDuration(SoundClip* clip);
+uint64_t clipPosition(SoundClip* clip);
+int clipState(SoundClip* clip);
+float clipVolume(SoundClip* clip);
+float clipSpeed(SoundClip* clip);
+ma_uint64 clipEnd(SoundClip* clip);
+ma_uint64 readFrames(SoundClip* clip, void* output, ma_uint64 frameCount);
+ma_uint64 stretchFrames(SoundClip* clip, void* output, ma_uint64 frameCount);
+bool stretchWindow(SoundClip* clip, ma_uint64 window, double tempo);
+void clearStretcher(Stretcher& stretcher);
+void reportProgress(SoundClip* clip, ma_uint64 frameCount);
+void reportLoops(SoundClip* clip, ma_uint64 before);
+bool detectSilence(SoundClip* clip, float peak, ma_uint64 frameCount);
+float processBlock(SoundClip* clip, void* frames, ma_uint64 frameCount);
+float samplePeak(const float* samples, ma_uint64 sampleCount);
+float sampleRms(const float* samples, ma_uint64 sampleCount);
+float groupGain(SoundClip* clip, float* samples, ma_uint64 sampleCount);
+float duckGain(SoundGroup* group, int64_t now);
+int64_t contextMicros(AudioContext* context);
+void applyFade(SoundClip* clip, float* samples, ma_uint64 frameCount, ma_uint32 channels);
+void finishFade(SoundClip* clip);
+void cancelFade(SoundClip* clip);
+void reportFadeStop(SoundClip* clip);
+float fadeShape(int curve, float from, float to, float progress);
+void applyPan(float pan, float* samples, ma_uint64 frameCount, ma_uint32 channels);
+void applySpatial(SoundClip* clip, float* samples, ma_uint64 frameCount, ma_uint32 channels);
+float distanceGain(int model, float distance);
+void pushBlock(SoundClip* clip, const float* samples, ma_uint64 sampleCount);
+void endBlockTap(SoundClip* clip);
@@ -2,70 +2,204 @@
 //! # Examples
 //! ## Minimal
 //! ```
-//! let context = Context::new().unwrap();
-//! let mut clip = AudioLoader::new("audio.mp3", context.clone())
+//! use ez_audio::*;
+//!
+//! // Context::new() plays on the default output device, a mock context needs no hardware
+//! let context = Context::mock().unwrap();
+//! let clip = AudioLoader::new("tests/fixtures/blip.wav", context.clone())
 //!     .load()
 //!     .unwrap();
-//! 
-//! clip.play();
-//! loop {}
+//!
+//! clip.play().unwrap();
+//! clip.wait();
 //! ```
 //! ## With on end
 //! ```
-//! let context = Context::new().unwrap();
-//! let mut clip = AudioLoader::new("audio.mp3", context.clone())
+//! use ez_audio::*;
+//!
+//! let context = Context::mock().unwrap();
+//! let clip = AudioLoader::new("tests/fixtures/blip.wav", context.clone())
 //!     .user_data(10)
 //!     .on_end(|data| {
-//!         assert!(data == 10)
+//!         assert!(*data == 10)
 //!     })
 //!     .load()
 //!     .unwrap();
-//! 
-//! clip.play();
-//! loop {}
+//!
+//! clip.play().unwrap();
+//! clip.wait();
 //! ```
-#![feature(fn_traits)]
-#![feature(unboxed_closures)]
-#![feature(get_mut_unchecked)]
 #![warn(missing_docs)]
 
+use std::collections::{HashMap, VecDeque};
 use std::ffi::{CStr, CString, OsStr};
-use std::fs::metadata;
+use std::fs::File;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
 use std::iter::Iterator;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex, RwLock};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, Once, RwLock, Weak};
+use std::task::{Poll, Waker};
 use std::time::Duration;
 
 use std::error::Error;
 use std::fmt;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 mod void;
 
-static mut ID: AtomicUsize = AtomicUsize::new(0);
+static ID: AtomicUsize = AtomicUsize::new(0);
+static FREE_ID: Mutex<Vec<usize>> = Mutex::new(Vec::new());
 
+//ids of removed clips are handed out again so the backend map stays small
 fn get_id() -> usize {
-    unsafe { ID.fetch_add(1, Ordering::Relaxed) }
+    FREE_ID
+        .lock()
+        .unwrap()
+        .pop()
+        .unwrap_or_else(|| ID.fetch_add(1, Ordering::Relaxed))
+}
+
+fn free_id(id: usize) {
+    FREE_ID.lock().unwrap().push(id);
+}
+
+/// The highest accepted volume, a gain of +20dB.
+pub const MAX_VOLUME: f32 = 10.0;
+
+//a single NaN would silence the whole output so it is treated as silence
+fn clamp_volume(volume: f32) -> f32 {
+    if volume.is_nan() {
+        0.0
+    } else {
+        volume.clamp(0.0, MAX_VOLUME)
+    }
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 struct AudioDevice {
     id: [u8; 256],
     name: *const c_char,
 }
 
+unsafe impl Send for AudioDevice {}
+
+impl Default for AudioDevice {
+    fn default() -> Self {
+        AudioDevice {
+            id: [0; 256],
+            name: std::ptr::null(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct DeviceFormat {
+    sample_rate: u32,
+    channels: u32,
+}
+
 #[repr(C)]
 struct AudioContext {
     context: usize,     //pointer not real usize
     sound_clips: usize, //pointer not real usize
     result: bool,
-    mtx: usize, //pointer not real usize
+    offline: bool,
+    mtx: usize,            //pointer not real usize
+    processor: usize,      //pointer not real usize
+    processor_user: usize, //pointer not real usize
+    state: usize,          //pointer not real usize
+}
+
+#[repr(C)]
+struct ClipConfig {
+    volume: f32,
+    match_device_rate: bool,
+    looping: bool,
+    group: *mut c_void,
+    range_start: u64,
+    range_end: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct ClipInfo {
+    sample_rate: u32,
+    channels: u32,
+    format: i32,
+}
+
+#[repr(C)]
+struct EngineConfig {
+    sample_rate: u32,
+    channels: u32,
+    buffer_frames: u32,
+    backend: i32,
+    share_mode: i32,
+}
+
+#[repr(C)]
+struct ReaderSource {
+    read: unsafe extern "C" fn(*mut c_void, *mut u8, usize) -> usize,
+    seek: unsafe extern "C" fn(*mut c_void, i64, i32) -> bool,
+    user: *mut c_void,
+}
+
+#[repr(C)]
+struct DecodedClip {
+    sample_rate: u32,
+    channels: u32,
+    samples: *const f32,
+    length: usize,
+    buffer: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct ContextUsage {
+    clips: usize,
+    playing: usize,
+    memory: usize,
+    budget: usize,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct ClipSnapshot {
+    volume: f32,
+    duration: u64,
+    state: i32,
+    pan: f32,
+    speed: f32,
+    looping: bool,
+    position: u64,
 }
 
 #[allow(improper_ctypes)]
 extern "C" {
-    fn init(end_callback: unsafe extern "C" fn(*mut InnerHandle<()>)) -> AudioContext;
+    fn init(end_callback: unsafe extern "C" fn(*const EndHook)) -> AudioContext;
+    fn initConfig(
+        end_callback: unsafe extern "C" fn(*const EndHook),
+        config: *const EngineConfig,
+    ) -> AudioContext;
+    fn getBackend(context: *const AudioContext) -> i32;
+    fn getVersion() -> *const c_char;
+    fn getDecoderError() -> *const c_char;
+    fn getEnabledBackends(backends: *mut i32, capacity: usize) -> usize;
+    fn getSoundCount(context: *const AudioContext) -> usize;
+    fn setMemoryBudget(context: *const AudioContext, bytes: usize);
+    fn getStats(context: *const AudioContext) -> ContextUsage;
+    fn initNull(end_callback: unsafe extern "C" fn(*const EndHook)) -> AudioContext;
+    fn initOffline(end_callback: unsafe extern "C" fn(*const EndHook)) -> AudioContext;
     fn uninit(context: *const AudioContext);
 
     fn load(
@@ -73,442 +207,4010 @@ extern "C" {
         context: *const AudioContext,
         path: *const c_char,
         device: *const AudioDevice,
+        config: *const ClipConfig,
+    ) -> i32;
+    #[cfg(windows)]
+    fn loadW(
+        id: usize,
+        context: *const AudioContext,
+        path: *const u16,
+        device: *const AudioDevice,
+        config: *const ClipConfig,
+    ) -> i32;
+    fn loadMemory(
+        id: usize,
+        context: *const AudioContext,
+        data: *const c_void,
+        size: usize,
+        device: *const AudioDevice,
+        config: *const ClipConfig,
+    ) -> i32;
+    fn decode(
+        path: *const c_char,
+        wide_path: *const u16,
+        data: *const c_void,
+        size: usize,
+        reader: *mut ReaderSource,
+        out: *mut DecodedClip,
+        progress: Option<unsafe extern "C" fn(*mut c_void, f32)>,
+        user: *mut c_void,
+    ) -> i32;
+    fn loadReader(
+        id: usize,
+        context: *const AudioContext,
+        reader: *const ReaderSource,
+        device: *const AudioDevice,
+        config: *const ClipConfig,
+    ) -> i32;
+    fn loadDecoded(
+        id: usize,
+        context: *const AudioContext,
+        clip: *const DecodedClip,
+        device: *const AudioDevice,
+        config: *const ClipConfig,
     ) -> i32;
-    fn setOuter(id: usize, context: *const AudioContext, outer: *const InnerHandle<()>);
+    fn loadGenerator(
+        id: usize,
+        context: *const AudioContext,
+        reader: *const ReaderSource,
+        sample_rate: u32,
+        channels: u32,
+        device: *const AudioDevice,
+        config: *const ClipConfig,
+    ) -> i32;
+    fn releaseDecoded(clip: *mut DecodedClip);
+    fn setOuter(id: usize, context: *const AudioContext, outer: *const EndHook);
     fn removeSound(id: usize, context: *const AudioContext);
 
-    fn play(id: usize, context: *const AudioContext);
-    fn stop(id: usize, context: *const AudioContext);
-    fn reset(id: usize, context: *const AudioContext);
+    fn play(id: usize, context: *const AudioContext) -> i32;
+    fn stop(id: usize, context: *const AudioContext) -> i32;
+    fn reset(id: usize, context: *const AudioContext) -> i32;
+    fn replay(id: usize, context: *const AudioContext) -> i32;
+    fn seek(id: usize, context: *const AudioContext, micros: u64) -> bool;
+    fn isSeekable(id: usize, context: *const AudioContext) -> bool;
     fn setVolume(id: usize, context: *const AudioContext, value: f32);
     fn getVolume(id: usize, context: *const AudioContext) -> f32;
+    fn fade(
+        id: usize,
+        context: *const AudioContext,
+        from: f32,
+        to: f32,
+        micros: u64,
+        stop: bool,
+        curve: i32,
+    );
 
-    fn isPlaying(id: usize, context: *const AudioContext) -> bool;
+    fn getState(id: usize, context: *const AudioContext) -> i32;
     fn getDuration(id: usize, context: *const AudioContext) -> u64;
+    fn getPosition(id: usize, context: *const AudioContext) -> u64;
+    fn advance(context: *const AudioContext, micros: u64) -> bool;
+    fn setProgressCallback(progress_callback: unsafe extern "C" fn(*const EndHook));
+    fn setDeviceLostCallback(device_lost_callback: unsafe extern "C" fn(*const EndHook));
+    fn setLoopCallback(loop_callback: unsafe extern "C" fn(*const EndHook));
+    fn takeLoops(id: usize, context: *const AudioContext) -> u64;
+    fn setFadeStopCallback(fade_stop_callback: unsafe extern "C" fn(*const EndHook));
+    fn setProgressInterval(id: usize, context: *const AudioContext, micros: u64);
+    fn getCodec(id: usize, context: *const AudioContext) -> i32;
+    fn getInfo(id: usize, context: *const AudioContext) -> ClipInfo;
+    fn getSnapshot(id: usize, context: *const AudioContext) -> ClipSnapshot;
+    fn isResampling(id: usize, context: *const AudioContext) -> bool;
+    fn setInstant(id: usize, context: *const AudioContext, instant: bool);
+    fn setReplayOnPlay(id: usize, context: *const AudioContext, replay: bool);
+    fn setLooping(id: usize, context: *const AudioContext, looping: bool);
+    fn isLooping(id: usize, context: *const AudioContext) -> bool;
+    fn setLoopRegion(
+        id: usize,
+        context: *const AudioContext,
+        start_micros: u64,
+        end_micros: u64,
+    ) -> bool;
+    fn setSilenceDetect(id: usize, context: *const AudioContext, threshold: f32, min_millis: u64);
+    fn isAudible(id: usize, context: *const AudioContext, threshold: f32) -> bool;
+    fn setPlaybackRate(id: usize, context: *const AudioContext, rate: u32);
+    fn getPlaybackRate(id: usize, context: *const AudioContext) -> u32;
+    fn setSpeed(id: usize, context: *const AudioContext, speed: f32);
+    fn getSpeed(id: usize, context: *const AudioContext) -> f32;
+    fn setTempo(id: usize, context: *const AudioContext, tempo: f32);
+    fn getTempo(id: usize, context: *const AudioContext) -> f32;
+    fn setPan(id: usize, context: *const AudioContext, pan: f32);
+    fn setPosition3d(id: usize, context: *const AudioContext, x: f32, y: f32, z: f32);
+    fn clearPosition3d(id: usize, context: *const AudioContext);
+    fn setAttenuation(id: usize, context: *const AudioContext, model: i32);
+    fn getAttenuation(id: usize, context: *const AudioContext) -> i32;
+    fn setListenerPosition(context: *const AudioContext, x: f32, y: f32, z: f32);
+    fn setListenerDirection(context: *const AudioContext, x: f32, y: f32, z: f32);
+    fn getPan(id: usize, context: *const AudioContext) -> f32;
+    fn setProcessorBypass(id: usize, context: *const AudioContext, bypass: bool);
+    fn getProcessorBypass(id: usize, context: *const AudioContext) -> bool;
+    fn clearEffects(id: usize, context: *const AudioContext);
+    fn getPreFaderLevel(id: usize, context: *const AudioContext) -> f32;
+    fn getPostFaderLevel(id: usize, context: *const AudioContext) -> f32;
+    fn getRmsLevel(id: usize, context: *const AudioContext) -> f32;
+    fn copySettings(
+        id: usize,
+        context: *const AudioContext,
+        other_id: usize,
+        other_context: *const AudioContext,
+    );
+    fn pauseEngine(context: *const AudioContext);
+    fn resumeEngine(context: *const AudioContext);
+    fn stopAll(context: *const AudioContext);
+    fn resumeAll(context: *const AudioContext);
+    fn isEnginePaused(context: *const AudioContext) -> bool;
+    fn setProcessor(
+        context: *const AudioContext,
+        processor: unsafe extern "C" fn(*const InnerContext, *mut f32, u32, u32),
+        user: *const InnerContext,
+    );
+
+    fn openBlockTap(id: usize, context: *const AudioContext) -> *mut c_void;
+    fn nextBlock(tap: *mut c_void, output: *mut f32, capacity: usize) -> i64;
+    fn closeBlockTap(tap: *mut c_void);
+
+    fn createGroup() -> *mut c_void;
+    fn setGroupVolume(group: *mut c_void, volume: f32);
+    fn getGroupVolume(group: *mut c_void) -> f32;
+    fn setGroupMuted(group: *mut c_void, muted: bool);
+    fn isGroupMuted(group: *mut c_void) -> bool;
+    fn setGroupDuck(
+        group: *mut c_void,
+        trigger: *mut c_void,
+        gain: f32,
+        attack_micros: u64,
+        release_micros: u64,
+    );
+    fn setMasterVolume(context: *const AudioContext, volume: f32);
+    fn getMasterVolume(context: *const AudioContext) -> f32;
+    fn releaseGroup(group: *mut c_void);
 
     fn getDefaultAudioDevice(context: *const AudioContext) -> AudioDevice;
+    fn getDeviceFormat(
+        context: *const AudioContext,
+        device: *const AudioDevice,
+        capture: bool,
+        format: *mut DeviceFormat,
+    ) -> bool;
+    fn getLatency(context: *const AudioContext, device: *const AudioDevice) -> u64;
+    fn getShareMode(context: *const AudioContext) -> i32;
+    fn tryGetDefaultAudioDevice(context: *const AudioContext, device: *mut AudioDevice) -> bool;
     fn getAudioDevices(
         context: *const AudioContext,
         devices: *const AudioDevice,
         capacity: usize,
     ) -> usize;
     fn getAudioDeviceCount(context: &AudioContext) -> usize;
+    fn getInputDevices(
+        context: *const AudioContext,
+        devices: *const AudioDevice,
+        capacity: usize,
+    ) -> usize;
+    fn getInputDeviceCount(context: &AudioContext) -> usize;
+
+    fn openRecorder(context: *const AudioContext, device: *const AudioDevice) -> *mut c_void;
+    fn startRecorder(recorder: *mut c_void) -> bool;
+    fn stopRecorder(recorder: *mut c_void);
+    fn isRecording(recorder: *mut c_void) -> bool;
+    fn getRecorderChannels(recorder: *mut c_void) -> u32;
+    fn getRecorderSampleRate(recorder: *mut c_void) -> u32;
+    fn getRecordedLength(recorder: *mut c_void) -> usize;
+    fn takeRecorded(recorder: *mut c_void, output: *mut f32, capacity: usize) -> usize;
+    fn closeRecorder(context: *const AudioContext, recorder: *mut c_void);
     fn setAudioDevice(id: usize, context: *const AudioContext, device: *const AudioDevice);
+    fn getClipDevice(id: usize, context: *const AudioContext, device: *mut AudioDevice);
+    fn setContextAudioDevice(context: *const AudioContext, device: *const AudioDevice);
 }
 
 /// A general purpose error.
 #[derive(Debug, Clone)]
 pub enum AudioError {
-    /// Unable to find file.
-    FileError,
+    /// Unable to open file.
+    /// Contains the error returned when the file was opened.
+    FileError(Arc<std::io::Error>),
     /// Unable to decode file.
-    DecoderError,
+    /// Contains the description of the failure given by the decoder.
+    DecoderError(String),
+    /// No decoder recognized the contents of the file.
+    UnsupportedFormat,
+    /// Loading the clip would exceed the memory budget of the context, see
+    /// [`Context::set_memory_budget`].
+    OutOfMemory,
     /// Error getting audio device.
     DeviceError,
     /// Error initializing backend.
     ContextError,
-    /// Catch all error that should never occur.
-    /// If it occurs it is a sign of undefined behavior. 
-    UnknownError,
+    /// The clip could not be moved to the requested position.
+    SeekError,
+    /// The end of a time range is not after its start, or the range starts past the end of the clip.
+    InvalidRange,
+    /// The configuration of a [`ContextBuilder`] is outside of what the backend supports.
+    UnsupportedConfig,
+    /// The backend returned an error code that is not mapped to any other variant.
+    /// Contains the raw return code.
+    UnknownError(i32),
 }
 
 impl Error for AudioError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+        match self {
+            AudioError::FileError(error) => Some(error.as_ref()),
+            _ => None,
+        }
     }
 }
 
 impl fmt::Display for AudioError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            AudioError::FileError => write!(f, "unable to find file"),
-            AudioError::DecoderError => write!(f, "unable to decode file"),
+        match self {
+            AudioError::FileError(error) => write!(f, "unable to open file: {}", error),
+            AudioError::DecoderError(reason) => write!(f, "unable to decode file: {}", reason),
+            AudioError::UnsupportedFormat => write!(f, "unsupported audio format"),
+            AudioError::OutOfMemory => write!(f, "memory budget of the context exceeded"),
             AudioError::DeviceError => write!(f, "invalid device"),
             AudioError::ContextError => write!(f, "unable to initialize context"),
-            AudioError::UnknownError => unreachable!(),
+            AudioError::SeekError => write!(f, "unable to seek"),
+            AudioError::InvalidRange => write!(f, "invalid time range"),
+            AudioError::UnsupportedConfig => write!(f, "unsupported context configuration"),
+            AudioError::UnknownError(code) => {
+                write!(f, "an unknown backend error occurred (code {})", code)
+            }
         }
     }
 }
 
-/// Yields default output device.
-pub fn default_output_device(context: Context) -> Device {
-    Device {
-        device: unsafe { getDefaultAudioDevice(&context.inner.context) },
-        _context: context,
-    }
+/// The codec used to decode an audio file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    /// Waveform Audio File Format.
+    Wav,
+    /// Free Lossless Audio Codec.
+    Flac,
+    /// MPEG-1 Audio Layer III.
+    Mp3,
+    /// Ogg Vorbis.
+    Vorbis,
+    /// The decoder could not be identified.
+    Unknown,
 }
 
-/// A handle to an audio playback device.
-pub struct Device {
-    device: AudioDevice,
-    _context: Context,
+/// The playback state of an audio handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PlaybackState {
+    /// The clip is playing.
+    Playing,
+    /// The clip was paused somewhere after its start.
+    Paused,
+    /// The clip is at its start, either because it was never played, it was reset or it reached its end.
+    Stopped,
 }
 
-unsafe impl Send for Device {}
-unsafe impl Sync for Device {}
-
-impl<'a> Device {
-    /// Returns name of audio device.
-    pub fn name(&self) -> &'a str {
-        unsafe {
-            CStr::from_ptr(self.device.name)
-                .to_str()
-                .unwrap_or("Undefined")
+impl PlaybackState {
+    fn from_raw(raw: i32) -> PlaybackState {
+        match raw {
+            2 => PlaybackState::Playing,
+            1 => PlaybackState::Paused,
+            _ => PlaybackState::Stopped,
         }
     }
 }
 
-/// Yields an iterator over all audio playback devices.
-pub fn output_devices(context: Context) -> Devices {
-    unsafe {
-        let capacity = getAudioDeviceCount(&context.inner.context);
-        let mut devices: Vec<AudioDevice> = Vec::with_capacity(capacity);
-        let ptr = devices.as_mut_ptr();
-        std::mem::forget(devices);
-        let len = getAudioDevices(&context.inner.context, ptr, capacity);
+/// How a [`Playlist`] continues once a track has ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Playback stops after the last track.
+    Off,
+    /// The current track is played again.
+    One,
+    /// Playback continues with the first track after the last one.
+    All,
+}
 
-        let devices = Vec::from_raw_parts(ptr, len, capacity);
+/// The shape of a volume ramp.
+///
+/// Hearing is logarithmic, so a linear ramp seems to jump near silence and then barely change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FadeCurve {
+    /// The volume changes at a constant rate.
+    Linear,
+    /// The volume changes slowly near silence and quickly near full volume,
+    /// which sounds even to the ear.
+    #[default]
+    Exponential,
+    /// The volume changes quickly near silence and slowly near full volume.
+    Logarithmic,
+    /// The volume follows a quarter of a sine wave, so a fade in and a fade out
+    /// of the same length keep a constant power together.
+    Sine,
+}
 
-        Devices { devices, context }
+impl FadeCurve {
+    fn to_raw(self) -> i32 {
+        match self {
+            FadeCurve::Linear => 0,
+            FadeCurve::Exponential => 1,
+            FadeCurve::Logarithmic => 2,
+            FadeCurve::Sine => 3,
+        }
     }
 }
 
-/// A iterator that yields audio devices.
-pub struct Devices {
-    devices: Vec<AudioDevice>,
-    context: Context,
+/// How clips share the output device with other applications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShareMode {
+    /// The device is mixed with other applications by the operating system.
+    #[default]
+    Shared,
+    /// The device is used without the mixer of the operating system, which has the lowest
+    /// latency but locks out every other user of the device.
+    Exclusive,
 }
 
-impl<'a> Iterator for Devices {
-    type Item = Device;
-    fn next(&mut self) -> Option<Self::Item> {
-        let option = self.devices.pop();
-        if let Some(device) = option {
-            Some(Device {
-                device,
-                _context: self.context.clone(),
-            })
-        } else {
-            None
+impl ShareMode {
+    fn to_raw(self) -> i32 {
+        match self {
+            ShareMode::Shared => 0,
+            ShareMode::Exclusive => 1,
+        }
+    }
+
+    fn from_raw(mode: i32) -> ShareMode {
+        match mode {
+            1 => ShareMode::Exclusive,
+            _ => ShareMode::Shared,
         }
     }
 }
 
-#[no_mangle]
-unsafe extern "C" fn end_callback(inner_handle: *mut InnerHandle<()>) {
-    (*inner_handle).on_end();
+/// The gain curves of the two clips in a [`crossfade`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrossfadeCurve {
+    /// Both gains change linearly, so the amplitudes sum to a constant.
+    /// Uncorrelated material sounds quieter in the middle of the fade.
+    #[default]
+    Linear,
+    /// The gains follow a sine and a cosine, so the power sums to a constant.
+    /// This keeps the loudness level when mixing unrelated tracks.
+    ConstantPower,
 }
 
-struct InnerContext {
-    context: AudioContext,
+impl CrossfadeCurve {
+    fn fade_curve(self) -> FadeCurve {
+        match self {
+            CrossfadeCurve::Linear => FadeCurve::Linear,
+            CrossfadeCurve::ConstantPower => FadeCurve::Sine,
+        }
+    }
 }
 
-/// A handle to a backend context.
-#[derive(Clone)]
-pub struct Context {
-    inner: Arc<InnerContext>,
+/// The format of a single sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Unsigned 8 bit integer.
+    U8,
+    /// Signed 16 bit integer.
+    S16,
+    /// Signed 24 bit integer.
+    S24,
+    /// Signed 32 bit integer.
+    S32,
+    /// 32 bit float.
+    F32,
+    /// The format could not be identified.
+    Unknown,
 }
 
-unsafe impl Send for Context {}
-unsafe impl Sync for Context {}
+/// An audio backend of the operating system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The first backend that is available, in the default order of miniaudio.
+    Auto,
+    /// Windows Audio Session API.
+    Wasapi,
+    /// DirectSound on Windows.
+    DirectSound,
+    /// Windows Multimedia.
+    WinMm,
+    /// Core Audio on macOS and iOS.
+    CoreAudio,
+    /// sndio on OpenBSD.
+    Sndio,
+    /// audio(4) on NetBSD and OpenBSD.
+    Audio4,
+    /// Open Sound System.
+    Oss,
+    /// PulseAudio.
+    PulseAudio,
+    /// Advanced Linux Sound Architecture.
+    Alsa,
+    /// JACK Audio Connection Kit.
+    Jack,
+    /// AAudio on Android.
+    AAudio,
+    /// OpenSL ES on Android.
+    OpenSl,
+    /// Web Audio in the browser.
+    WebAudio,
+    /// A virtual backend without any hardware.
+    Null,
+}
 
-impl Context {
-    /// Creates new backend context
-    pub fn new() -> Result<Self, AudioError> {
-        unsafe {
-            let context = init(end_callback);
-            if context.result {
-                Ok(Context {
-                    inner: Arc::new(InnerContext { context }),
-                })
-            } else {
-                Err(AudioError::ContextError)
-            }
+impl Backend {
+    //indices of ma_backend
+    fn to_raw(self) -> i32 {
+        match self {
+            Backend::Auto => -1,
+            Backend::Wasapi => 0,
+            Backend::DirectSound => 1,
+            Backend::WinMm => 2,
+            Backend::CoreAudio => 3,
+            Backend::Sndio => 4,
+            Backend::Audio4 => 5,
+            Backend::Oss => 6,
+            Backend::PulseAudio => 7,
+            Backend::Alsa => 8,
+            Backend::Jack => 9,
+            Backend::AAudio => 10,
+            Backend::OpenSl => 11,
+            Backend::WebAudio => 12,
+            Backend::Null => 14,
         }
     }
-}
 
-impl Drop for InnerContext {
-    fn drop(&mut self) {
-        unsafe {
-            uninit(&self.context);
+    fn from_raw(backend: i32) -> Backend {
+        match backend {
+            0 => Backend::Wasapi,
+            1 => Backend::DirectSound,
+            2 => Backend::WinMm,
+            3 => Backend::CoreAudio,
+            4 => Backend::Sndio,
+            5 => Backend::Audio4,
+            6 => Backend::Oss,
+            7 => Backend::PulseAudio,
+            8 => Backend::Alsa,
+            9 => Backend::Jack,
+            10 => Backend::AAudio,
+            11 => Backend::OpenSl,
+            12 => Backend::WebAudio,
+            _ => Backend::Null,
         }
     }
 }
 
-/// A builder that loads an audio file into memory and returns an audio playback handle.
-pub struct AudioLoader<'a, T, I, P> {
-    path: P,
-    context: Context,
-    device: Option<&'a Device>,
-    volume: f32,
-    on_end: Option<I>,
-    user_data: T,
+/// Returns the version of miniaudio, the C library that does the decoding and playback.
+pub fn miniaudio_version() -> &'static str {
+    //the version is a string literal in the library
+    unsafe { CStr::from_ptr(getVersion()) }
+        .to_str()
+        .unwrap_or("Undefined")
 }
 
-impl<'a, P> AudioLoader<'a, (), void::Void, P>
-where
-    P: AsRef<Path>,
-{
-    /// Creates a new default audio loader.
-    pub fn new(path: P, context: Context) -> AudioLoader<'a, (), void::Void, P> {
-        AudioLoader {
-            path,
-            context,
-            device: None,
-            volume: 1f32,
-            on_end: None,
-            user_data: (),
-        }
-    }
+/// Returns the backends compiled into this build, in the order they are tried.
+///
+/// A backend in this list may still be unavailable at runtime, see [`Context::backend`]
+/// for the one that is actually used.
+pub fn available_backends() -> Vec<Backend> {
+    let mut backends = [0i32; 16];
+    let len = unsafe { getEnabledBackends(backends.as_mut_ptr(), backends.len()) };
+    backends[..len]
+        .iter()
+        .map(|backend| Backend::from_raw(*backend))
+        .collect()
 }
 
-impl<'a, T, I, P> AudioLoader<'a, T, I, P>
-where
-    P: AsRef<Path>,
-    I: 'static + FnMut(&mut T) + Send,
-{
-    /// Set context.
-    pub fn context(mut self, context: Context) -> Self {
-        self.context = context;
-        self
-    }
+/// How the volume of a positioned clip falls off with its distance to the listener.
+///
+/// Clips within one unit of the listener are never attenuated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttenuationModel {
+    /// The volume does not depend on the distance.
+    None,
+    /// The volume falls off linearly and reaches silence at 100 units.
+    Linear,
+    /// The volume is inversely proportional to the distance, like sound in open air.
+    Exponential,
+}
 
-    /// Set playback device
-    pub fn device(mut self, device: &'a Device) -> Self {
-        self.device = Some(device);
-        self
+impl AttenuationModel {
+    fn to_raw(self) -> i32 {
+        match self {
+            AttenuationModel::None => 0,
+            AttenuationModel::Linear => 1,
+            AttenuationModel::Exponential => 2,
+        }
     }
 
-    /// Set playback volume
-    pub fn volume(mut self, volume: f32) -> Self {
-        self.volume = volume;
-        self
+    fn from_raw(model: i32) -> AttenuationModel {
+        match model {
+            1 => AttenuationModel::Linear,
+            2 => AttenuationModel::Exponential,
+            _ => AttenuationModel::None,
+        }
     }
+}
 
-    /// Destroys loader and returns a audio handle
-    pub fn load(self) -> Result<AudioHandle<T>, AudioError> {
-        if metadata(self.path.as_ref()).is_err() {
-            return Err(AudioError::FileError);
-        };
+/// The native properties of an audio file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioInfo {
+    /// Sample rate in hz.
+    pub sample_rate: u32,
+    /// Number of channels.
+    pub channels: u16,
+    /// Format of the samples.
+    pub format: SampleFormat,
+}
 
-        unsafe {
-            let id = get_id();
-            let result = load(
-                id,
-                &self.context.inner.context,
-                #[allow(temporary_cstring_as_ptr)]
-                CString::new(self.path.as_ref().as_os_str().to_str().unwrap())
-                    .unwrap()
-                    .as_ptr(),
-                &self
-                    .device
-                    .unwrap_or(&default_output_device(self.context.clone()))
-                    .device,
-            );
+/// A whole audio file decoded to memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedAudio {
+    /// Interleaved samples in the range `-1.0..=1.0`.
+    pub samples: Vec<f32>,
+    /// Sample rate in hz.
+    pub sample_rate: u32,
+    /// Number of channels.
+    pub channels: u16,
+}
 
-            let res = match result {
-                0 => Ok(AudioHandle {
-                    inner: Arc::new(InnerHandle {
-                        id,
-                        path: self.path.as_ref().to_path_buf(),
-                        context: self.context.clone(),
-                        user_data: RwLock::new(Arc::new(self.user_data)),
-                        on_end: {
-                            if let Some(on_end) = self.on_end {
-                                Some(Mutex::new(Box::new(on_end)))
-                            } else {
-                                None
-                            }
-                        },
-                    }),
-                }),
-                -1 => Err(AudioError::DecoderError),
-                -2 => Err(AudioError::DeviceError),
-                _ => Err(AudioError::UnknownError),
-            };
+impl DecodedAudio {
+    /// Returns the minimum and maximum amplitude of each of `buckets` equal slices of the audio.
+    ///
+    /// The channels are mixed down by averaging them, which is what is usually drawn
+    /// as a waveform overview.
+    pub fn waveform(&self, buckets: usize) -> Vec<(f32, f32)> {
+        let channels = self.channels.max(1) as usize;
+        self.peaks(buckets, |frame| frame.iter().sum::<f32>() / channels as f32)
+    }
 
-            if res.is_ok() {
-                setOuter(
-                    id,
-                    &self.context.inner.context,
-                    Arc::as_ptr(&res.as_ref().unwrap().inner) as *const InnerHandle<()>,
-                );
-            }
-            res
+    /// Returns the minimum and maximum amplitude of each of `buckets` equal slices of one channel.
+    ///
+    /// Returns an empty vector if the channel does not exist.
+    pub fn channel_waveform(&self, channel: u16, buckets: usize) -> Vec<(f32, f32)> {
+        if channel >= self.channels {
+            return Vec::new();
         }
+        self.peaks(buckets, |frame| frame[channel as usize])
     }
-}
 
-impl<'a, T, I, P0> AudioLoader<'a, T, I, P0> {
-    /// Set path to file.
-    pub fn path<P1: AsRef<Path>>(self, path: P1) -> AudioLoader<'a, T, I, P1> {
-        AudioLoader {
-            path,
-            context: self.context,
-            device: self.device,
-            volume: self.volume,
-            on_end: self.on_end,
-            user_data: self.user_data,
+    //every bucket covers at least one frame so short audio is stretched instead of leaving gaps
+    fn peaks(&self, buckets: usize, sample: impl Fn(&[f32]) -> f32) -> Vec<(f32, f32)> {
+        let frames: Vec<&[f32]> = self
+            .samples
+            .chunks_exact(self.channels.max(1) as usize)
+            .collect();
+        if frames.is_empty() {
+            return vec![(0.0, 0.0); buckets];
         }
+        (0..buckets)
+            .map(|bucket| {
+                let start = (bucket * frames.len() / buckets).min(frames.len() - 1);
+                let end = ((bucket + 1) * frames.len() / buckets).max(start + 1);
+                frames[start..end]
+                    .iter()
+                    .map(|frame| sample(frame))
+                    .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    })
+            })
+            .collect()
     }
 }
 
-impl<'a, T0, I, P> AudioLoader<'a, T0, I, P> {
-    /// Sets custom userdata
-    pub fn user_data<T1>(self, user_data: T1) -> AudioLoader<'a, T1, I, P> {
-        AudioLoader {
-            path: self.path,
-            context: self.context,
-            device: self.device,
-            volume: self.volume,
-            on_end: self.on_end,
-            user_data,
-        }
-    }
+/// Resource usage of a context, see [`Context::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContextStats {
+    /// Number of loaded clips.
+    pub clips: usize,
+    /// Number of clips that are playing.
+    pub playing: usize,
+    /// Bytes of in memory sources held by the clips, see [`Context::set_memory_budget`].
+    pub memory: usize,
+    /// The memory budget, zero if there is none.
+    pub memory_budget: usize,
 }
 
-impl<'a, T, F0: Fn(T), P> AudioLoader<'a, T, F0, P> {
-    /// Sets closure to be run when the audio handle reaches the end.
-    pub fn on_end<F1: FnMut(&mut T) + Send>(self, on_end: F1) -> AudioLoader<'a, T, F1, P> {
-        AudioLoader {
-            path: self.path,
-            context: self.context,
-            device: self.device,
-            volume: self.volume,
-            on_end: Some(on_end),
-            user_data: self.user_data,
-        }
-    }
+/// A point-in-time view of the state of an audio handle.
+///
+/// All fields are read at once so they are consistent with each other.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HandleSnapshot {
+    /// Path used to create the handle.
+    pub path: PathBuf,
+    /// Playback volume, following a fade that is in progress.
+    pub volume: f32,
+    /// Duration of the clip.
+    pub duration: Duration,
+    /// Playback position.
+    pub position: Duration,
+    /// Whether the clip was playing, paused or stopped.
+    pub state: PlaybackState,
+    /// Stereo pan, see [`AudioHandle::set_pan`].
+    pub pan: f32,
+    /// Playback speed, see [`AudioHandle::set_speed`].
+    pub speed: f32,
+    /// If the clip loops.
+    pub looping: bool,
 }
 
-struct InnerHandle<T> {
-    id: usize,
-    path: PathBuf,
-    context: Context,
-    user_data: RwLock<Arc<T>>,
-    #[allow(clippy::type_complexity)]
-    on_end: Option<Mutex<Box<dyn FnMut(&mut T) + Send>>>,
+/// Yields default output device.
+///
+/// If the system has no output device the returned device is invalid and loading
+/// onto it will fail. Prefer [`try_default_output_device`] to detect that case.
+pub fn default_output_device(context: Context) -> Device {
+    let device = context.with(|context| unsafe { getDefaultAudioDevice(context) });
+    Device::new(device, false, context)
 }
 
-impl<T> InnerHandle<T> {
-    fn on_end(&mut self) {
-        if let Some(closure) = &mut self.on_end {
-            let mut refrence = self.user_data.write().unwrap();
-            unsafe {
-                let user_data = Arc::get_mut_unchecked(&mut refrence);
-                (closure.get_mut().unwrap())(user_data);
-            }
-        }
+/// Yields default output device or [`AudioError::DeviceError`] if there is none.
+pub fn try_default_output_device(context: Context) -> Result<Device, AudioError> {
+    let mut device = AudioDevice::default();
+    if context.with(|context| unsafe { tryGetDefaultAudioDevice(context, &mut device) }) {
+        Ok(Device::new(device, false, context))
+    } else {
+        Err(AudioError::DeviceError)
     }
 }
 
-/// A handle that can be used to control audio playback.
-pub struct AudioHandle<T> {
-    inner: Arc<InnerHandle<T>>,
-}
+/// The backend id of a device.
+///
+/// Unlike the name it is unique, and with the `serde` feature it can be stored to find the
+/// device again with [`Context::output_device_by_id`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeviceId([u8; 256]);
 
-impl<T> AudioHandle<T> {
-    /// Starts playing audio.
-    pub fn play(&self) {
-        unsafe {
-            play(self.inner.id, &self.inner.context.inner.context);
-        }
+impl DeviceId {
+    //ids are mostly zero padding which is left out of the text form
+    fn to_hex(self) -> String {
+        let len = self
+            .0
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |last| last + 1);
+        self.0[..len]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
     }
 
-    /// Pauses playback.
-    pub fn stop(&self) {
-        unsafe {
-            stop(self.inner.id, &self.inner.context.inner.context);
+    #[cfg(feature = "serde")]
+    fn from_hex(hex: &str) -> Option<DeviceId> {
+        let digits = hex.as_bytes().chunks_exact(2);
+        if !digits.remainder().is_empty() || hex.len() > 512 || !hex.is_ascii() {
+            return None;
+        }
+        let mut id = [0u8; 256];
+        for (byte, digits) in id.iter_mut().zip(digits) {
+            *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
         }
+        Some(DeviceId(id))
     }
+}
+
+impl fmt::Debug for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DeviceId").field(&self.to_hex()).finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for DeviceId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for DeviceId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        DeviceId::from_hex(&hex).ok_or_else(|| serde::de::Error::custom("invalid device id"))
+    }
+}
+
+/// A handle to an audio playback device.
+pub struct Device {
+    device: AudioDevice,
+    name: String,
+    capture: bool,
+    context: Context,
+}
+
+unsafe impl Send for Device {}
+unsafe impl Sync for Device {}
+
+//devices are identified by the backend id since names are not unique
+impl PartialEq for Device {
+    fn eq(&self, other: &Self) -> bool {
+        self.device.id == other.device.id
+    }
+}
+
+impl Eq for Device {}
+
+impl Hash for Device {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.device.id.hash(state);
+    }
+}
+
+impl fmt::Debug for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Device")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Device {
+    //the backend reuses its name buffers when devices are enumerated again so the name is copied out
+    fn new(device: AudioDevice, capture: bool, context: Context) -> Device {
+        let name = if device.name.is_null() {
+            String::from("Undefined")
+        } else {
+            unsafe { CStr::from_ptr(device.name) }
+                .to_str()
+                .unwrap_or("Undefined")
+                .to_string()
+        };
+        Device {
+            device,
+            name,
+            capture,
+            context,
+        }
+    }
+
+    /// Returns name of audio device.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the backend id of the device.
+    pub fn id(&self) -> DeviceId {
+        DeviceId(self.device.id)
+    }
+
+    //opens the device with its native settings to see what the backend picks
+    fn format(&self) -> DeviceFormat {
+        let mut format = DeviceFormat::default();
+        self.context.with(|context| unsafe {
+            getDeviceFormat(context, &self.device, self.capture, &mut format)
+        });
+        format
+    }
+
+    /// Returns the number of channels the device runs at natively.
+    ///
+    /// Returns 0 if the device could not be opened.
+    pub fn channels(&self) -> u16 {
+        self.format().channels as u16
+    }
+
+    /// Returns the sample rate the device runs at natively.
+    ///
+    /// Clips with a different sample rate are resampled during playback.
+    /// Returns 0 if the device could not be opened.
+    pub fn sample_rate(&self) -> u32 {
+        self.format().sample_rate
+    }
+}
+
+/// Yields an iterator over all audio playback devices.
+pub fn output_devices(context: Context) -> Devices {
+    let devices = context.with(|inner| unsafe {
+        let capacity = getAudioDeviceCount(inner);
+        let mut devices: Vec<AudioDevice> = Vec::with_capacity(capacity);
+        let ptr = devices.as_mut_ptr();
+        std::mem::forget(devices);
+        let len = getAudioDevices(inner, ptr, capacity);
+
+        Vec::from_raw_parts(ptr, len, capacity)
+    });
+
+    Devices {
+        devices,
+        capture: false,
+        context,
+    }
+}
+
+/// Yields an iterator over all audio capture devices.
+pub fn input_devices(context: Context) -> Devices {
+    let devices = context.with(|inner| unsafe {
+        let capacity = getInputDeviceCount(inner);
+        let mut devices: Vec<AudioDevice> = Vec::with_capacity(capacity);
+        let len = getInputDevices(inner, devices.as_mut_ptr(), capacity);
+        devices.set_len(len);
+        devices
+    });
+
+    Devices {
+        devices,
+        capture: true,
+        context,
+    }
+}
+
+/// A iterator that yields audio devices.
+pub struct Devices {
+    devices: Vec<AudioDevice>,
+    capture: bool,
+    context: Context,
+}
+
+impl Iterator for Devices {
+    type Item = Device;
+    fn next(&mut self) -> Option<Self::Item> {
+        let option = self.devices.pop();
+        if let Some(device) = option {
+            Some(Device::new(device, self.capture, self.context.clone()))
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.devices.len(), Some(self.devices.len()))
+    }
+}
+
+impl DoubleEndedIterator for Devices {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.devices.is_empty() {
+            return None;
+        }
+        let device = self.devices.remove(0);
+        Some(Device::new(device, self.capture, self.context.clone()))
+    }
+}
+
+impl ExactSizeIterator for Devices {}
+
+/// A type erased pointer to a handle that the backend passes back when its clip ends.
+struct EndHook {
+    call: unsafe fn(*const ()),
+    device_lost: unsafe fn(*const ()),
+    looped: unsafe fn(*const ()),
+    fade_stopped: unsafe fn(*const ()),
+    handle: *const (),
+    progress: Arc<ProgressSignal>,
+    loops: Arc<ProgressSignal>,
+}
+
+unsafe impl Send for EndHook {}
+unsafe impl Sync for EndHook {}
+
+#[no_mangle]
+unsafe extern "C" fn end_callback(hook: *const EndHook) {
+    ((*hook).call)((*hook).handle);
+}
+
+unsafe fn end_hook<T>(handle: *const ()) {
+    (*(handle as *const InnerHandle<T>)).on_end();
+    release_hook::<T>(handle);
+}
+
+unsafe extern "C" fn fade_stop_callback(hook: *const EndHook) {
+    ((*hook).fade_stopped)((*hook).handle);
+}
+
+//the handle kept by fade_out_and_release may be the last one so nothing is touched after it is dropped
+unsafe fn release_hook<T>(handle: *const ()) {
+    let release = (*(handle as *const InnerHandle<T>))
+        .release
+        .lock()
+        .unwrap()
+        .take();
+    drop(release);
+}
+
+unsafe extern "C" fn device_lost_callback(hook: *const EndHook) {
+    ((*hook).device_lost)((*hook).handle);
+}
+
+unsafe fn device_lost_hook<T>(handle: *const ()) {
+    let inner = &*(handle as *const InnerHandle<T>);
+    if let Some(closure) = &mut *inner.device_lost.lock().unwrap() {
+        closure();
+    }
+}
+
+//offline clips report their loops from advance where the closure can run right away,
+//live clips report them from the audio thread which only wakes the loop thread of the handle
+unsafe extern "C" fn loop_callback(hook: *const EndHook) {
+    ((*hook).looped)((*hook).handle);
+}
+
+unsafe fn loop_hook<T>(handle: *const ()) {
+    let inner = &*(handle as *const InnerHandle<T>);
+    if inner.context.inner.context.offline {
+        inner.on_loop();
+    } else {
+        inner.end_hook.loops.notify();
+    }
+}
+
+//called on the audio thread so it only wakes the progress thread of the handle
+unsafe extern "C" fn progress_callback(hook: *const EndHook) {
+    (*hook).progress.notify();
+}
+
+/// Wakes the thread that runs the progress or loop closure of a handle.
+#[derive(Default)]
+struct ProgressSignal {
+    state: Mutex<ProgressState>,
+    cv: Condvar,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    pending: bool,
+    //bumped whenever the closure is replaced so the previous thread exits
+    generation: u64,
+}
+
+impl ProgressSignal {
+    fn notify(&self) {
+        self.state.lock().unwrap().pending = true;
+        self.cv.notify_one();
+    }
+
+    fn restart(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.pending = false;
+        self.cv.notify_one();
+        state.generation
+    }
+
+    /// Waits for the next report, returns false once the thread of `generation` should exit.
+    fn wait(&self, generation: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        while !state.pending && state.generation == generation {
+            state = self.cv.wait(state).unwrap();
+        }
+        state.pending = false;
+        state.generation == generation
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn process_callback(
+    inner_context: *const InnerContext,
+    samples: *mut f32,
+    frames: u32,
+    channels: u32,
+) {
+    //clips share the processor without waiting for each other, only a block that arrives
+    //while it is being replaced passes through since the audio thread never waits
+    let processor = match (*inner_context).processor.try_read() {
+        Ok(processor) => processor,
+        Err(_) => return,
+    };
+    if let Some(processor) = &*processor {
+        let samples = std::slice::from_raw_parts_mut(samples, (frames * channels) as usize);
+        processor(samples, channels);
+    }
+}
+
+type Processor = Box<dyn Fn(&mut [f32], u32) + Send + Sync>;
+type DeviceChange = Box<dyn FnMut(Context) + Send>;
+
+/// Interval at which the device list is checked for [`Context::on_device_change`].
+const DEVICE_POLL: Duration = Duration::from_millis(500);
+
+struct InnerContext {
+    context: AudioContext,
+    output_device: Mutex<Option<AudioDevice>>,
+    processor: RwLock<Option<Processor>>,
+    buses: Mutex<HashMap<String, SoundGroup>>,
+    device_change: Mutex<Option<DeviceChange>>,
+    device_watcher: Once,
+    end_events: Mutex<Vec<Sender<usize>>>,
+    closed: RwLock<bool>,
+}
+
+unsafe impl Send for InnerContext {}
+unsafe impl Sync for InnerContext {}
+
+/// A handle to a backend context.
+#[derive(Clone)]
+pub struct Context {
+    inner: Arc<InnerContext>,
+}
+
+unsafe impl Send for Context {}
+unsafe impl Sync for Context {}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context").finish_non_exhaustive()
+    }
+}
+
+impl Context {
+    /// Creates new backend context
+    ///
+    /// Backends are tried in the default order of miniaudio, see [`Context::backend`] for the
+    /// one that was picked. Returns [`AudioError::ContextError`] if no backend could be
+    /// initialized. A backend without an output device makes loading clips fail with
+    /// [`AudioError::DeviceError`]. Use [`Context::mock`] for a context that works
+    /// without audio hardware.
+    pub fn new() -> Result<Self, AudioError> {
+        unsafe { Self::from_raw(init(end_callback)) }
+    }
+
+    /// Creates new backend context that plays on `device` by default.
+    ///
+    /// Clips loaded without an explicit device are played on `device` instead of the system default.
+    pub fn for_device(device: &Device) -> Result<Self, AudioError> {
+        let context = Self::new()?;
+        *context.inner.output_device.lock().unwrap() = Some(device.device);
+        Ok(context)
+    }
+
+    /// Creates a backend context that uses miniaudio's null backend.
+    ///
+    /// Clips loaded on a mock context play on a virtual device that consumes audio
+    /// in real time without any hardware, so `play`, `duration` and `on_end`
+    /// behave like on a real device. Useful for testing in headless environments.
+    pub fn mock() -> Result<Self, AudioError> {
+        unsafe { Self::from_raw(initNull(end_callback)) }
+    }
+
+    /// Creates a backend context whose clock only moves when [`Context::advance`] is called.
+    ///
+    /// Like [`Context::mock`] it uses miniaudio's null backend, but no audio is consumed
+    /// in the background, which makes playback fully deterministic.
+    pub fn offline() -> Result<Self, AudioError> {
+        unsafe { Self::from_raw(initOffline(end_callback)) }
+    }
+
+    /// Returns the backend the context is running on.
+    ///
+    /// A closed context reports [`Backend::Null`].
+    pub fn backend(&self) -> Backend {
+        self.with(|context| Some(unsafe { getBackend(context) }))
+            .map_or(Backend::Null, Backend::from_raw)
+    }
+
+    /// Returns the share mode clips are played with.
+    ///
+    /// This is [`ShareMode::Shared`] if exclusive mode was requested but is not available.
+    pub fn share_mode(&self) -> ShareMode {
+        self.with(|context| Some(unsafe { getShareMode(context) }))
+            .map_or(ShareMode::Shared, ShareMode::from_raw)
+    }
+
+    /// Returns the output latency of the current output device.
+    ///
+    /// This is the length of the buffer the device was opened with, which is how far
+    /// the audio handed to the device runs ahead of the speaker. The device is opened
+    /// briefly to measure it. Returns [`Duration::ZERO`] if the device could not be opened.
+    pub fn latency(&self) -> Duration {
+        let device = self.output_device();
+        let micros = self.with(|context| unsafe { getLatency(context, &device) });
+        Duration::from_micros(micros)
+    }
+
+    /// Returns the number of clips loaded on the context.
+    ///
+    /// A clip is counted until the last [`AudioHandle`] to it is dropped.
+    pub fn active_sound_count(&self) -> usize {
+        self.with(|context| unsafe { getSoundCount(context) })
+    }
+
+    /// Limits the memory held by clips loaded from memory or from decoded audio to `bytes`.
+    ///
+    /// Loading such a clip beyond the budget fails with [`AudioError::OutOfMemory`]. Clips
+    /// that share one [`DecodedAudio`], like the voices of a [`SoundPool`], count it once.
+    /// Clips streamed from a file, a reader or a generator do not count. Clips that are
+    /// already loaded are kept when the budget is lowered. Passing `0` removes the budget.
+    pub fn set_memory_budget(&self, bytes: usize) {
+        self.with(|context| unsafe { setMemoryBudget(context, bytes) });
+    }
+
+    /// Returns the number of clips, how many of them play and the memory they hold.
+    pub fn stats(&self) -> ContextStats {
+        let usage = self.with(|context| unsafe { getStats(context) });
+        ContextStats {
+            clips: usage.clips,
+            playing: usage.playing,
+            memory: usage.memory,
+            memory_budget: usage.budget,
+        }
+    }
+
+    /// Advances the clock of an offline context by `duration`.
+    ///
+    /// Every playing clip consumes that much audio, and the loop closures of clips that
+    /// wrapped around and the `on_end` callbacks of clips that reach their end are run
+    /// before this returns.
+    /// Returns [`AudioError::ContextError`] if the context was not created with [`Context::offline`].
+    pub fn advance(&self, duration: Duration) -> Result<(), AudioError> {
+        if self.with(|context| unsafe { advance(context, duration.as_micros() as u64) }) {
+            Ok(())
+        } else {
+            Err(AudioError::ContextError)
+        }
+    }
+
+    /// Moves every clip of the context to `device`.
+    ///
+    /// Clips keep their position, volume and whether they were playing.
+    /// Clips loaded afterwards without an explicit device will also use `device`.
+    pub fn set_output_device(&self, device: &Device) {
+        let mut output_device = self.inner.output_device.lock().unwrap();
+        *output_device = Some(device.device);
+        self.with(|context| unsafe {
+            setContextAudioDevice(context, output_device.as_ref().unwrap())
+        });
+    }
+
+    /// Sets the volume applied on top of every clip and group on the context.
+    ///
+    /// The volume is clamped like [`AudioHandle::set_volume`].
+    pub fn set_master_volume(&self, volume: f32) {
+        self.with(|context| unsafe { setMasterVolume(context, clamp_volume(volume)) });
+    }
+
+    /// Returns the master volume of the context.
+    ///
+    /// A closed context reports 0.
+    pub fn master_volume(&self) -> f32 {
+        self.with(|context| unsafe { getMasterVolume(context) })
+    }
+
+    /// Pauses time for the whole context.
+    ///
+    /// Every playing clip is paused, and clips that are started with [`AudioHandle::play`]
+    /// while the engine is paused only start once it is resumed.
+    pub fn pause_engine(&self) {
+        self.with(|context| unsafe { pauseEngine(context) });
+    }
+
+    /// Resumes all clips that were paused by [`Context::pause_engine`] or started while it was paused.
+    ///
+    /// Clips that were paused with [`AudioHandle::stop`] in the meantime stay paused.
+    pub fn resume_engine(&self) {
+        self.with(|context| unsafe { resumeEngine(context) });
+    }
+
+    /// Moves the listener that positioned clips are heard from.
+    ///
+    /// The coordinate system is right handed with `+y` pointing up, and the listener starts
+    /// at the origin facing `-z`, so `+x` is to its right.
+    pub fn set_listener_position(&self, x: f32, y: f32, z: f32) {
+        self.with(|context| unsafe { setListenerPosition(context, x, y, z) });
+    }
+
+    /// Turns the listener to face `x`, `y`, `z`.
+    ///
+    /// The direction does not have to be normalized. A listener facing straight up or down
+    /// has no left or right, so positioned clips are only attenuated and not panned.
+    pub fn set_listener_direction(&self, x: f32, y: f32, z: f32) {
+        self.with(|context| unsafe { setListenerDirection(context, x, y, z) });
+    }
+
+    /// Pauses every clip that is playing.
+    ///
+    /// Unlike [`Context::pause_engine`] clips can still be played afterwards.
+    pub fn stop_all(&self) {
+        self.with(|context| unsafe { stopAll(context) });
+    }
+
+    /// Resumes the clips that were paused by [`Context::stop_all`].
+    ///
+    /// Clips that were paused with [`AudioHandle::stop`] in the meantime stay paused.
+    pub fn resume_all(&self) {
+        self.with(|context| unsafe { resumeAll(context) });
+    }
+
+    /// Checks if the engine is paused.
+    pub fn is_engine_paused(&self) -> bool {
+        self.with(|context| unsafe { isEnginePaused(context) })
+    }
+
+    /// Inserts a custom processing step into the output of every clip.
+    ///
+    /// `process` receives interleaved `f32` samples and the channel count of the clip
+    /// and modifies the samples in place. There is no node graph or final mix: every clip
+    /// has its own output stream, so the closure is called once per block of each playing
+    /// clip, before fades, pan and volume are applied.
+    ///
+    /// The closure runs on the audio threads and must not block or allocate if glitches are
+    /// to be avoided. It is shared by all clips, which call it at the same time from their
+    /// own audio threads, so it is `Fn + Sync` and state that has to be kept between blocks
+    /// belongs in atomics or per channel count. Only a block that arrives while the
+    /// processor is being replaced passes through unprocessed. Inserting a new processor
+    /// replaces the previous one. Single clips can skip it with
+    /// [`AudioHandle::set_custom_node_bypass`].
+    pub fn insert_custom_node<F>(&self, process: F)
+    where
+        F: 'static + Fn(&mut [f32], u32) + Send + Sync,
+    {
+        *self.inner.processor.write().unwrap() = Some(Box::new(process));
+        self.with(|context| unsafe {
+            setProcessor(context, process_callback, Arc::as_ptr(&self.inner))
+        });
+    }
+
+    /// Returns the bus called `name`, creating it on first use.
+    ///
+    /// Clips are routed to a bus with [`AudioLoader::bus_name`]. Every call with the same
+    /// name returns the same group.
+    pub fn bus(&self, name: &str) -> SoundGroup {
+        self.inner
+            .buses
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(SoundGroup::new)
+            .clone()
+    }
+
+    /// Returns a channel that receives the [`AudioHandle::id`] of every clip on the context
+    /// that reaches its end.
+    ///
+    /// Ids are sent after the `on_end` closure of the handle has run. Every call returns a
+    /// new receiver that gets every end, and dropping a receiver unsubscribes it.
+    pub fn end_events(&self) -> Receiver<usize> {
+        let (sender, receiver) = channel();
+        self.inner.end_events.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Registers a closure that is called when devices are added or removed or the
+    /// default output device changes.
+    ///
+    /// The backend has no device notifications, so the device list is polled on a
+    /// separate thread and the closure runs on that thread, never on the audio thread.
+    /// It receives the context so devices can be enumerated again with [`output_devices`].
+    /// Registering a new closure replaces the previous one.
+    pub fn on_device_change<F>(&self, on_change: F)
+    where
+        F: 'static + FnMut(Context) + Send,
+    {
+        *self.inner.device_change.lock().unwrap() = Some(Box::new(on_change));
+        self.inner.device_watcher.call_once(|| {
+            let weak = Arc::downgrade(&self.inner);
+            let signature = self.device_signature();
+            std::thread::spawn(move || Self::watch_devices(weak, signature));
+        });
+    }
+
+    fn watch_devices(weak: Weak<InnerContext>, mut signature: Vec<String>) {
+        loop {
+            std::thread::sleep(DEVICE_POLL);
+            let context = match weak.upgrade() {
+                Some(inner) => Context { inner },
+                None => return,
+            };
+            if *context.inner.closed.read().unwrap() {
+                return;
+            }
+            let current = context.device_signature();
+            if current == signature {
+                continue;
+            }
+            signature = current;
+
+            //the closure is taken out so it can register a replacement without deadlocking
+            let on_change = context.inner.device_change.lock().unwrap().take();
+            if let Some(mut on_change) = on_change {
+                on_change(context.clone());
+                let mut slot = context.inner.device_change.lock().unwrap();
+                if slot.is_none() {
+                    *slot = Some(on_change);
+                }
+            }
+        }
+    }
+
+    /// Names of the default output device and all devices, used to detect changes.
+    fn device_signature(&self) -> Vec<String> {
+        let mut signature = vec![try_default_output_device(self.clone())
+            .map(|device| device.name().to_string())
+            .unwrap_or_default()];
+        signature.extend(output_devices(self.clone()).map(|device| device.name().to_string()));
+        signature.extend(input_devices(self.clone()).map(|device| device.name().to_string()));
+        signature
+    }
+
+    /// Finds the output device with the id `id`.
+    ///
+    /// Returns `None` if the device is no longer available.
+    pub fn output_device_by_id(&self, id: &DeviceId) -> Option<Device> {
+        output_devices(self.clone()).find(|device| device.id() == *id)
+    }
+
+    fn output_device(&self) -> AudioDevice {
+        match *self.inner.output_device.lock().unwrap() {
+            Some(device) => device,
+            None => self.with(|context| unsafe { getDefaultAudioDevice(context) }),
+        }
+    }
+
+    /// Releases the backend and all audio devices immediately.
+    ///
+    /// Handles and devices created from this context stay valid, but all of their operations
+    /// become no-ops and getters return default values. Loading new clips returns
+    /// [`AudioError::ContextError`].
+    pub fn close(self) {
+        let mut closed = self.inner.closed.write().unwrap();
+        if !*closed {
+            *closed = true;
+            unsafe {
+                uninit(&self.inner.context);
+            }
+        }
+    }
+
+    /// Runs `f` with the backend context unless the context was closed.
+    fn with<R: Default>(&self, f: impl FnOnce(&AudioContext) -> R) -> R {
+        let closed = self.inner.closed.read().unwrap();
+        if *closed {
+            R::default()
+        } else {
+            f(&self.inner.context)
+        }
+    }
+
+    fn from_raw(context: AudioContext) -> Result<Self, AudioError> {
+        if context.result {
+            unsafe {
+                setProgressCallback(progress_callback);
+                setDeviceLostCallback(device_lost_callback);
+                setLoopCallback(loop_callback);
+                setFadeStopCallback(fade_stop_callback);
+            }
+            Ok(Context {
+                inner: Arc::new(InnerContext {
+                    context,
+                    output_device: Mutex::new(None),
+                    processor: RwLock::new(None),
+                    buses: Mutex::new(HashMap::new()),
+                    device_change: Mutex::new(None),
+                    device_watcher: Once::new(),
+                    end_events: Mutex::new(Vec::new()),
+                    closed: RwLock::new(false),
+                }),
+            })
+        } else {
+            Err(AudioError::ContextError)
+        }
+    }
+}
+
+impl Drop for InnerContext {
+    fn drop(&mut self) {
+        if !*self.closed.get_mut().unwrap() {
+            unsafe {
+                uninit(&self.context);
+            }
+        }
+    }
+}
+
+//limits of the backend for ContextBuilder
+const MIN_SAMPLE_RATE: u32 = 8000;
+const MAX_SAMPLE_RATE: u32 = 384000;
+const MAX_CHANNELS: u16 = 32;
+
+/// A builder for a [`Context`] with a custom output format.
+///
+/// Every clip loaded on the context is converted to this format before it is played.
+/// Settings that are not set keep today's behavior of using the format of the file
+/// and the buffer size of the device.
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    sample_rate: u32,
+    channels: u16,
+    buffer_frames: u32,
+    backend: Backend,
+    share_mode: ShareMode,
+}
+
+impl ContextBuilder {
+    /// Creates a new default context builder.
+    pub fn new() -> ContextBuilder {
+        ContextBuilder {
+            sample_rate: 0,
+            channels: 0,
+            buffer_frames: 0,
+            backend: Backend::Auto,
+            share_mode: ShareMode::Shared,
+        }
+    }
+
+    /// Set the backend that is tried first.
+    ///
+    /// If it is not available the other backends are tried in the default order,
+    /// see [`Context::backend`] for the one that was picked.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Set the sample rate clips are played at.
+    ///
+    /// Files are converted to this rate when they are loaded, except for clips loaded with
+    /// [`AudioLoader::match_device_rate`] which keep the rate of the file.
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// Set the number of channels clips are played with.
+    ///
+    /// Panning only has an effect with two channels.
+    pub fn channels(mut self, channels: u16) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Set the size of the device buffer in frames.
+    ///
+    /// Smaller buffers lower the latency but are more likely to cause glitches.
+    /// The backend recovers from underruns without reporting them, so the buffer is never
+    /// grown automatically and has to be raised here on hardware that glitches.
+    pub fn buffer_frames(mut self, buffer_frames: u32) -> Self {
+        self.buffer_frames = buffer_frames;
+        self
+    }
+
+    /// Set how clips share the output device with other applications.
+    ///
+    /// [`ShareMode::Exclusive`] is checked on the default device when the context is built.
+    /// If it is unavailable the context falls back to shared mode, see [`Context::share_mode`].
+    /// Every clip opens its own device, so a clip that can not get exclusive access because
+    /// another clip holds it is played in shared mode.
+    pub fn share_mode(mut self, share_mode: ShareMode) -> Self {
+        self.share_mode = share_mode;
+        self
+    }
+
+    /// Creates the context.
+    ///
+    /// Returns [`AudioError::UnsupportedConfig`] if the sample rate is not between 8000 and
+    /// 384000 hz or there are more than 32 channels.
+    pub fn build(self) -> Result<Context, AudioError> {
+        if self.sample_rate != 0 && !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(&self.sample_rate)
+        {
+            return Err(AudioError::UnsupportedConfig);
+        }
+        if self.channels > MAX_CHANNELS {
+            return Err(AudioError::UnsupportedConfig);
+        }
+        let config = EngineConfig {
+            sample_rate: self.sample_rate,
+            channels: self.channels as u32,
+            buffer_frames: self.buffer_frames,
+            backend: self.backend.to_raw(),
+            share_mode: self.share_mode.to_raw(),
+        };
+        unsafe { Context::from_raw(initConfig(end_callback, &config)) }
+    }
+}
+
+impl Default for ContextBuilder {
+    fn default() -> Self {
+        ContextBuilder::new()
+    }
+}
+
+/// A builder that loads an audio file into memory and returns an audio playback handle.
+///
+/// All settings are applied inside [`AudioLoader::load`] before the handle is returned,
+/// so the clip is fully configured before it can be played.
+pub struct AudioLoader<'a, T, I, P> {
+    path: P,
+    context: Context,
+    device: Option<&'a Device>,
+    volume: f32,
+    match_device_rate: bool,
+    looping: bool,
+    autoplay: bool,
+    start: Duration,
+    end: Option<Duration>,
+    bus: Option<Route>,
+    memory: Option<Vec<u8>>,
+    reader: Option<Reader>,
+    //the reader yields raw samples of a generator instead of an encoded file
+    generator: bool,
+    decoded: Option<Arc<DecodedAudio>>,
+    load_progress: Option<LoadProgress>,
+    on_end: Option<I>,
+    user_data: T,
+}
+
+impl<'a, P> AudioLoader<'a, (), void::Void, P>
+where
+    P: AsRef<Path>,
+{
+    /// Creates a new default audio loader.
+    pub fn new(path: P, context: Context) -> AudioLoader<'a, (), void::Void, P> {
+        AudioLoader {
+            path,
+            context,
+            device: None,
+            volume: 1f32,
+            match_device_rate: false,
+            looping: false,
+            autoplay: false,
+            start: Duration::ZERO,
+            end: None,
+            bus: None,
+            memory: None,
+            reader: None,
+            generator: false,
+            decoded: None,
+            load_progress: None,
+            on_end: None,
+            user_data: (),
+        }
+    }
+}
+
+impl<'a> AudioLoader<'a, (), void::Void, PathBuf> {
+    /// Creates a new default audio loader with an owned path.
+    ///
+    /// Loaders created this way all share the same type which makes them easy to store in collections.
+    pub fn new_owned(path: PathBuf, context: Context) -> AudioLoader<'a, (), void::Void, PathBuf> {
+        AudioLoader::new(path, context)
+    }
+
+    /// Creates a new default audio loader that decodes `bytes` instead of a file.
+    ///
+    /// The format is detected from the contents of the buffer, which is kept alive by the
+    /// returned handle. A buffer that can not be decoded returns [`AudioError::DecoderError`].
+    pub fn from_memory(
+        bytes: Vec<u8>,
+        context: Context,
+    ) -> AudioLoader<'a, (), void::Void, PathBuf> {
+        let mut loader = AudioLoader::new(PathBuf::new(), context);
+        loader.memory = Some(bytes);
+        loader
+    }
+
+    /// Creates a new default audio loader that decodes the bytes of `reader`.
+    ///
+    /// The format is detected from the contents, and the reader is kept alive by the returned
+    /// handle which streams from it during playback. A reader that can not be decoded returns
+    /// [`AudioError::DecoderError`].
+    pub fn from_reader<R: Read + Seek + Send + 'static>(
+        reader: R,
+        context: Context,
+    ) -> AudioLoader<'a, (), void::Void, PathBuf> {
+        let mut loader = AudioLoader::new(PathBuf::new(), context);
+        loader.reader = Some(Reader(Box::new(Box::new(reader))));
+        loader
+    }
+
+    /// Creates a new default audio loader that plays samples produced by `generator`.
+    ///
+    /// The generator is called on the audio thread with a buffer of interleaved samples
+    /// that is cleared to silence, and fills it with the next samples to play. Samples are
+    /// pulled at the native sample rate and channel count of the output device, see
+    /// [`Device::sample_rate`] and [`Device::channels`], or at 48000 hz in stereo if the
+    /// device could not be opened to check. The clip has no end and a duration of zero,
+    /// but [`AudioHandle::play`], [`AudioHandle::stop`] and the volume and effects work as
+    /// on any other clip. Only [`AudioHandle::reset`] can seek it, which does not rewind
+    /// the generator.
+    pub fn from_generator<F: FnMut(&mut [f32]) + Send + 'static>(
+        generator: F,
+        context: Context,
+    ) -> AudioLoader<'a, (), void::Void, PathBuf> {
+        let mut loader = AudioLoader::new(PathBuf::new(), context);
+        loader.reader = Some(Reader(Box::new(Box::new(Generator {
+            generator,
+            samples: Vec::new(),
+            bytes: Vec::new(),
+            offset: 0,
+        }))));
+        loader.generator = true;
+        loader
+    }
+
+    /// Creates a new default audio loader that plays already decoded samples.
+    ///
+    /// The samples are shared with the returned handle instead of being copied, so many
+    /// clips can be loaded from one [`AudioLoader::decode`] without decoding again.
+    pub fn from_decoded(
+        audio: Arc<DecodedAudio>,
+        context: Context,
+    ) -> AudioLoader<'a, (), void::Void, PathBuf> {
+        let mut loader = AudioLoader::new(PathBuf::new(), context);
+        loader.decoded = Some(audio);
+        loader
+    }
+}
+
+impl<'a, T, I, P> AudioLoader<'a, T, I, P>
+where
+    P: AsRef<Path>,
+    I: void::OnEnd<T>,
+{
+    /// Set context.
+    pub fn context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Set playback device
+    pub fn device(mut self, device: &'a Device) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Set playback volume
+    ///
+    /// The volume is clamped to `0.0..=`[`MAX_VOLUME`] and NaN is treated as `0.0`.
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = clamp_volume(volume);
+        self
+    }
+
+    /// Request the output device to run at the sample rate of the file.
+    ///
+    /// This opens the device in exclusive mode, which is the only way to avoid resampling.
+    /// If exclusive mode is unavailable the device is opened in shared mode, where the
+    /// device rate is fixed by the system and the clip may still be resampled.
+    /// The clip keeps the rate of the file even if the context was built with
+    /// [`ContextBuilder::sample_rate`]. Use [`AudioHandle::is_resampling`] to check the outcome.
+    pub fn match_device_rate(mut self, match_device_rate: bool) -> Self {
+        self.match_device_rate = match_device_rate;
+        self
+    }
+
+    /// Restart the clip from the beginning whenever it reaches its end.
+    ///
+    /// `on_end` is not called while the clip is looping.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Calls `on_load_progress` with the fraction of the file that has been decoded.
+    ///
+    /// The fraction goes from `0.0` to `1.0` while [`AudioLoader::decode`] reads the whole file.
+    /// [`AudioLoader::load`] streams the file during playback so it never calls the closure.
+    /// If the length of the file is unknown only `1.0` is reported once decoding has finished.
+    pub fn on_load_progress<F: FnMut(f32) + Send + 'static>(mut self, on_load_progress: F) -> Self {
+        self.load_progress = Some(Box::new(on_load_progress));
+        self
+    }
+
+    /// Start playing the clip as soon as it is loaded.
+    ///
+    /// If playback can not be started loading fails with the error of [`AudioHandle::play`].
+    pub fn autoplay(mut self, autoplay: bool) -> Self {
+        self.autoplay = autoplay;
+        self
+    }
+
+    /// Play only the part of the clip between `start` and `end`.
+    ///
+    /// The handle treats the range as the whole clip: [`AudioHandle::duration`] reports its
+    /// length, positions are relative to `start` and the clip rewinds to `start` when it is
+    /// reset or reaches `end`. An `end` past the end of the file is clamped to it.
+    /// Loading returns [`AudioError::InvalidRange`] if `end` is not after `start`.
+    pub fn range(mut self, start: Duration, end: Duration) -> Self {
+        self.start = start;
+        self.end = Some(end);
+        self
+    }
+
+    /// Play the clip from `start` to the end of the file.
+    ///
+    /// See [`AudioLoader::range`].
+    pub fn start_at(mut self, start: Duration) -> Self {
+        self.start = start;
+        self.end = None;
+        self
+    }
+
+    /// Route the clip to the bus called `name`.
+    ///
+    /// The bus is created if it does not exist yet, see [`Context::bus`].
+    pub fn bus_name(mut self, name: &str) -> Self {
+        self.bus = Some(Route::Bus(name.to_string()));
+        self
+    }
+
+    /// Route the clip to `group`.
+    ///
+    /// Replaces a bus set with [`AudioLoader::bus_name`].
+    pub fn group(mut self, group: &SoundGroup) -> Self {
+        self.bus = Some(Route::Group(group.clone()));
+        self
+    }
+
+    /// Destroys loader and returns a audio handle
+    pub fn load(self) -> Result<AudioHandle<T>, AudioError> {
+        let device = self.resolve_device();
+        self.load_on(device)
+    }
+
+    /// Loads a new audio handle and keeps the loader so it can load more.
+    ///
+    /// Every handle gets a clone of the user data and of the `on_end` closure. The bytes of a
+    /// loader created with [`AudioLoader::from_memory`] are copied for every handle, use
+    /// [`AudioLoader::from_decoded`] to share them instead. A reader can only be loaded once,
+    /// so a loader created with [`AudioLoader::from_reader`] returns [`AudioError::FileError`].
+    /// The closure set with [`AudioLoader::on_load_progress`] is not run.
+    pub fn load_handle(&self) -> Result<AudioHandle<T>, AudioError>
+    where
+        T: Clone,
+        I: Clone,
+        P: Clone,
+    {
+        if self.reader.is_some() {
+            return Err(AudioError::FileError(Arc::new(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "a reader can only be loaded once",
+            ))));
+        }
+        let loader = AudioLoader {
+            path: self.path.clone(),
+            context: self.context.clone(),
+            device: self.device,
+            volume: self.volume,
+            match_device_rate: self.match_device_rate,
+            looping: self.looping,
+            autoplay: self.autoplay,
+            start: self.start,
+            end: self.end,
+            bus: self.bus.clone(),
+            memory: self.memory.clone(),
+            reader: None,
+            generator: false,
+            decoded: self.decoded.clone(),
+            load_progress: None,
+            on_end: self.on_end.clone(),
+            user_data: self.user_data.clone(),
+        };
+        loader.load()
+    }
+
+    fn resolve_device(&self) -> AudioDevice {
+        self.device
+            .map(|device| device.device)
+            .unwrap_or_else(|| self.context.output_device())
+    }
+
+    /// Decodes the whole file to memory without playing it.
+    ///
+    /// Only the source of the loader is used. Samples are converted to f32 at the
+    /// native sample rate and channel count of the file.
+    pub fn decode(mut self) -> Result<DecodedAudio, AudioError> {
+        if let Some(audio) = &self.decoded {
+            return Ok(DecodedAudio::clone(audio));
+        }
+        if self.generator {
+            return Err(AudioError::DecoderError(
+                "a generator has no end to decode to".to_string(),
+            ));
+        }
+        if !self.has_source() {
+            check_file(self.path.as_ref())?;
+        }
+        let path = NativePath::new(self.path.as_ref()).ok_or_else(invalid_path)?;
+        let (path, wide_path) = if self.has_source() {
+            (std::ptr::null(), std::ptr::null())
+        } else {
+            path.as_ptrs()
+        };
+        let mut reader = self.reader.as_mut().map(Reader::source);
+        let reader = reader
+            .as_mut()
+            .map_or(std::ptr::null_mut(), |reader| reader as *mut ReaderSource);
+        let (data, size) = self.memory.as_ref().map_or((std::ptr::null(), 0), |bytes| {
+            (bytes.as_ptr() as *const c_void, bytes.len())
+        });
+
+        unsafe {
+            let mut clip = DecodedClip {
+                sample_rate: 0,
+                channels: 0,
+                samples: std::ptr::null(),
+                length: 0,
+                buffer: std::ptr::null_mut(),
+            };
+            let (callback, progress) = match self.load_progress.as_mut() {
+                Some(progress) => (
+                    Some(load_progress_callback as unsafe extern "C" fn(*mut c_void, f32)),
+                    progress as *mut LoadProgress as *mut c_void,
+                ),
+                None => (None, std::ptr::null_mut()),
+            };
+            match decode(
+                path, wide_path, data, size, reader, &mut clip, callback, progress,
+            ) {
+                0 => {
+                    //an empty vector may hand out a null pointer
+                    let samples = if clip.length == 0 {
+                        Vec::new()
+                    } else {
+                        std::slice::from_raw_parts(clip.samples, clip.length).to_vec()
+                    };
+                    releaseDecoded(&mut clip);
+                    Ok(DecodedAudio {
+                        samples,
+                        sample_rate: clip.sample_rate,
+                        channels: clip.channels as u16,
+                    })
+                }
+                -1 => Err(decoder_error()),
+                -3 => Err(AudioError::UnsupportedFormat),
+                code => Err(AudioError::UnknownError(code)),
+            }
+        }
+    }
+
+    /// Decodes the whole file and returns the minimum and maximum amplitude of each of
+    /// `buckets` equal slices of it, with the channels mixed down.
+    ///
+    /// See [`DecodedAudio::waveform`], use [`AudioLoader::decode`] and
+    /// [`DecodedAudio::channel_waveform`] for the peaks of a single channel.
+    pub fn waveform(self, buckets: usize) -> Result<Vec<(f32, f32)>, AudioError> {
+        self.decode().map(|audio| audio.waveform(buckets))
+    }
+
+    //loaders from memory, a reader or decoded samples have no file
+    fn has_source(&self) -> bool {
+        self.memory.is_some() || self.reader.is_some() || self.decoded.is_some()
+    }
+
+    fn load_on(mut self, device: AudioDevice) -> Result<AudioHandle<T>, AudioError> {
+        if !self.has_source() {
+            check_file(self.path.as_ref())?;
+        }
+
+        if matches!(self.end, Some(end) if end <= self.start) {
+            return Err(AudioError::InvalidRange);
+        }
+
+        let group = self.bus.as_ref().map(|route| match route {
+            Route::Bus(name) => self.context.bus(name),
+            Route::Group(group) => group.clone(),
+        });
+
+        let closed = self.context.inner.closed.read().unwrap();
+        if *closed {
+            return Err(AudioError::ContextError);
+        }
+
+        //the path has to outlive the call to load
+        let path = NativePath::new(self.path.as_ref()).ok_or_else(invalid_path)?;
 
-    /// Resets to start of audio clip.
-    pub fn reset(&self) {
         unsafe {
-            reset(self.inner.id, &self.inner.context.inner.context);
+            let id = get_id();
+            let config = ClipConfig {
+                volume: self.volume,
+                match_device_rate: self.match_device_rate,
+                looping: self.looping,
+                group: group
+                    .as_ref()
+                    .map_or(std::ptr::null_mut(), |group| group.inner.group),
+                range_start: self.start.as_micros() as u64,
+                range_end: self.end.map_or(0, |end| end.as_micros() as u64),
+            };
+            let result = match (&self.decoded, &self.memory, &mut self.reader) {
+                (None, None, Some(reader)) if self.generator => {
+                    let mut format = DeviceFormat::default();
+                    if !getDeviceFormat(&self.context.inner.context, &device, false, &mut format) {
+                        format = DeviceFormat {
+                            sample_rate: 48000,
+                            channels: 2,
+                        };
+                    }
+                    loadGenerator(
+                        id,
+                        &self.context.inner.context,
+                        &reader.source(),
+                        format.sample_rate,
+                        format.channels,
+                        &device,
+                        &config,
+                    )
+                }
+                (Some(audio), _, _) => loadDecoded(
+                    id,
+                    &self.context.inner.context,
+                    &DecodedClip {
+                        sample_rate: audio.sample_rate,
+                        channels: audio.channels as u32,
+                        samples: audio.samples.as_ptr(),
+                        length: audio.samples.len(),
+                        buffer: std::ptr::null_mut(),
+                    },
+                    &device,
+                    &config,
+                ),
+                (None, Some(bytes), _) => loadMemory(
+                    id,
+                    &self.context.inner.context,
+                    bytes.as_ptr() as *const c_void,
+                    bytes.len(),
+                    &device,
+                    &config,
+                ),
+                (None, None, Some(reader)) => loadReader(
+                    id,
+                    &self.context.inner.context,
+                    &reader.source(),
+                    &device,
+                    &config,
+                ),
+                (None, None, None) => path.load(id, &self.context.inner.context, &device, &config),
+            };
+
+            let res = match result {
+                0 => {
+                    let path = self.path.as_ref().to_path_buf();
+                    let context = self.context.clone();
+                    let user_data = RwLock::new(self.user_data);
+                    let memory = self.memory;
+                    let reader = self.reader;
+                    let decoded = self.decoded;
+                    let on_end = Mutex::new(self.on_end.map(|on_end| on_end.into_callback()));
+                    Ok(AudioHandle {
+                        inner: Arc::new_cyclic(|weak| InnerHandle {
+                            id,
+                            path,
+                            context,
+                            reference: Mutex::new(ReferenceGain {
+                                level: 1f32,
+                                user_volume: None,
+                            }),
+                            muted: Mutex::new(None),
+                            user_data,
+                            memory,
+                            reader,
+                            decoded,
+                            on_end,
+                            on_loop: Mutex::new(None),
+                            loop_count: AtomicU64::new(0),
+                            release: Mutex::new(None),
+                            device_lost: Mutex::new(None),
+                            ended: EndSignal::default(),
+                            end_hook: EndHook {
+                                call: end_hook::<T>,
+                                device_lost: device_lost_hook::<T>,
+                                looped: loop_hook::<T>,
+                                fade_stopped: release_hook::<T>,
+                                progress: Arc::new(ProgressSignal::default()),
+                                loops: Arc::new(ProgressSignal::default()),
+                                handle: weak.as_ptr() as *const (),
+                            },
+                        }),
+                    })
+                }
+                -1 => Err(decoder_error()),
+                -2 => Err(AudioError::DeviceError),
+                -3 => Err(AudioError::UnsupportedFormat),
+                -4 => Err(AudioError::SeekError),
+                -5 => Err(AudioError::InvalidRange),
+                -7 => Err(AudioError::OutOfMemory),
+                code => Err(AudioError::UnknownError(code)),
+            };
+
+            //the outer is set before autoplay so an end is always delivered to the handle
+            match &res {
+                Ok(handle) => setOuter(id, &self.context.inner.context, &handle.inner.end_hook),
+                Err(_) => free_id(id),
+            }
+            drop(closed);
+            if let (Ok(handle), true) = (&res, self.autoplay) {
+                handle.play()?;
+            }
+            res
+        }
+    }
+}
+
+trait ReadSeek: Read + Seek + Send {}
+
+impl<R: Read + Seek + Send> ReadSeek for R {}
+
+/// A reader that the backend pulls encoded bytes from.
+///
+/// It is boxed twice so the backend gets a thin pointer that stays put when the reader is moved.
+struct Reader(Box<Box<dyn ReadSeek>>);
+
+//only the backend touches the reader and it does so under the lock of its clip
+unsafe impl Sync for Reader {}
+
+impl Reader {
+    fn source(&mut self) -> ReaderSource {
+        ReaderSource {
+            read: reader_read,
+            seek: reader_seek,
+            user: &mut *self.0 as *mut Box<dyn ReadSeek> as *mut c_void,
+        }
+    }
+}
+
+/// Turns the samples of a generator into the raw bytes the backend reads.
+struct Generator<F> {
+    generator: F,
+    samples: Vec<f32>,
+    //bytes of the last call that did not fit into the read that asked for them
+    bytes: Vec<u8>,
+    offset: usize,
+}
+
+impl<F: FnMut(&mut [f32])> Read for Generator<F> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        if self.offset == self.bytes.len() {
+            self.samples.clear();
+            self.samples.resize((buffer.len() / 4).max(1), 0.0);
+            (self.generator)(&mut self.samples);
+            self.bytes.clear();
+            self.bytes
+                .extend(self.samples.iter().flat_map(|sample| sample.to_le_bytes()));
+            self.offset = 0;
+        }
+        let read = buffer.len().min(self.bytes.len() - self.offset);
+        buffer[..read].copy_from_slice(&self.bytes[self.offset..self.offset + read]);
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+//a generator can not go back so only the rewinds to the start are accepted
+impl<F> Seek for Generator<F> {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        match position {
+            SeekFrom::Start(0) => Ok(0),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "a generator can not seek",
+            )),
+        }
+    }
+}
+
+type LoadProgress = Box<dyn FnMut(f32) + Send>;
+
+//the file is opened up front because the decoder can not tell why it failed to open it
+fn check_file(path: &Path) -> Result<(), AudioError> {
+    File::open(path)
+        .map(drop)
+        .map_err(|error| AudioError::FileError(Arc::new(error)))
+}
+
+fn invalid_path() -> AudioError {
+    AudioError::FileError(Arc::new(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "path contains a nul character",
+    )))
+}
+
+//the description is stored per thread so it has to be read right after the failed call
+fn decoder_error() -> AudioError {
+    let reason = unsafe { CStr::from_ptr(getDecoderError()) };
+    AudioError::DecoderError(reason.to_string_lossy().into_owned())
+}
+
+unsafe extern "C" fn load_progress_callback(user: *mut c_void, fraction: f32) {
+    let progress = &mut *(user as *mut LoadProgress);
+    progress(fraction);
+}
+
+unsafe extern "C" fn reader_read(user: *mut c_void, buffer: *mut u8, size: usize) -> usize {
+    let reader = &mut *(user as *mut Box<dyn ReadSeek>);
+    let buffer = std::slice::from_raw_parts_mut(buffer, size);
+    //the backend treats a short read as the end of the data
+    let mut read = 0;
+    while read < size {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => break,
+            Ok(bytes) => read += bytes,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => break,
+        }
+    }
+    read
+}
+
+//an origin of 0 is the start of the data and anything else the current position
+unsafe extern "C" fn reader_seek(user: *mut c_void, offset: i64, origin: i32) -> bool {
+    let reader = &mut *(user as *mut Box<dyn ReadSeek>);
+    let position = match origin {
+        0 => SeekFrom::Start(offset as u64),
+        _ => SeekFrom::Current(offset),
+    };
+    reader.seek(position).is_ok()
+}
+
+/// A path in the encoding the backend opens files with.
+#[cfg(not(windows))]
+struct NativePath(CString);
+
+/// A path in the encoding the backend opens files with.
+#[cfg(windows)]
+struct NativePath(Vec<u16>);
+
+impl NativePath {
+    //fopen takes the raw bytes of the path so any path the OS accepts can be opened
+    #[cfg(unix)]
+    fn new(path: &Path) -> Option<NativePath> {
+        use std::os::unix::ffi::OsStrExt;
+        CString::new(path.as_os_str().as_bytes())
+            .ok()
+            .map(NativePath)
+    }
+
+    #[cfg(windows)]
+    fn new(path: &Path) -> Option<NativePath> {
+        use std::os::windows::ffi::OsStrExt;
+        let mut wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+        if wide.contains(&0) {
+            return None;
+        }
+        wide.push(0);
+        Some(NativePath(wide))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn new(path: &Path) -> Option<NativePath> {
+        CString::new(path.to_str()?).ok().map(NativePath)
+    }
+
+    #[cfg(not(windows))]
+    fn as_ptrs(&self) -> (*const c_char, *const u16) {
+        (self.0.as_ptr(), std::ptr::null())
+    }
+
+    #[cfg(windows)]
+    fn as_ptrs(&self) -> (*const c_char, *const u16) {
+        (std::ptr::null(), self.0.as_ptr())
+    }
+
+    #[cfg(not(windows))]
+    unsafe fn load(
+        &self,
+        id: usize,
+        context: &AudioContext,
+        device: &AudioDevice,
+        config: &ClipConfig,
+    ) -> i32 {
+        load(id, context, self.0.as_ptr(), device, config)
+    }
+
+    #[cfg(windows)]
+    unsafe fn load(
+        &self,
+        id: usize,
+        context: &AudioContext,
+        device: &AudioDevice,
+        config: &ClipConfig,
+    ) -> i32 {
+        loadW(id, context, self.0.as_ptr(), device, config)
+    }
+}
+
+/// Upper limit for the worker threads of [`AudioLoader::load_many`].
+const MAX_LOAD_WORKERS: usize = 4;
+
+impl<'a, T, I, P> AudioLoader<'a, T, I, P>
+where
+    P: AsRef<Path> + Send,
+    I: void::OnEnd<T>,
+    T: Send + Sync,
+{
+    /// Loads all `loaders` on a small pool of worker threads.
+    ///
+    /// Files are decoded in parallel and the results are returned in the same order as
+    /// `loaders`. A file that fails to load only fails its own entry.
+    pub fn load_many(loaders: Vec<Self>) -> Vec<Result<AudioHandle<T>, AudioError>> {
+        let count = loaders.len();
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |workers| workers.get())
+            .min(MAX_LOAD_WORKERS)
+            .min(count);
+        let queue = Mutex::new(loaders.into_iter().enumerate());
+        let results = Mutex::new((0..count).map(|_| None).collect::<Vec<_>>());
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap().next();
+                    match next {
+                        Some((index, loader)) => {
+                            let result = loader.load();
+                            results.lock().unwrap()[index] = Some(result);
+                        }
+                        None => break,
+                    }
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.unwrap())
+            .collect()
+    }
+
+    /// Loads the clip on a background thread and returns a future that resolves to the handle.
+    ///
+    /// The future works with any executor. Dropping it before it resolves releases the clip
+    /// as soon as the background load has finished.
+    pub fn load_async(self) -> LoadFuture<T>
+    where
+        P: 'static,
+        I: 'static,
+        T: 'static,
+    {
+        //the device is resolved up front so the loader no longer borrows it
+        let device = self.resolve_device();
+        let loader: AudioLoader<'static, T, I, P> = AudioLoader {
+            path: self.path,
+            context: self.context,
+            device: None,
+            volume: self.volume,
+            match_device_rate: self.match_device_rate,
+            looping: self.looping,
+            autoplay: self.autoplay,
+            start: self.start,
+            end: self.end,
+            bus: self.bus,
+            memory: self.memory,
+            reader: self.reader,
+            generator: self.generator,
+            decoded: self.decoded,
+            load_progress: self.load_progress,
+            on_end: self.on_end,
+            user_data: self.user_data,
+        };
+
+        let state = Arc::new(Mutex::new(LoadState {
+            result: None,
+            waker: None,
+            cancelled: false,
+        }));
+        let shared = state.clone();
+        std::thread::spawn(move || {
+            let result = loader.load_on(device);
+            let mut state = shared.lock().unwrap();
+            if state.cancelled {
+                //the handle is dropped after the lock is released
+                drop(state);
+                drop(result);
+                return;
+            }
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        LoadFuture { state }
+    }
+}
+
+struct LoadState<T> {
+    result: Option<Result<AudioHandle<T>, AudioError>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+}
+
+/// A future returned by [`AudioLoader::load_async`].
+pub struct LoadFuture<T> {
+    state: Arc<Mutex<LoadState<T>>>,
+}
+
+impl<T> Future for LoadFuture<T> {
+    type Output = Result<AudioHandle<T>, AudioError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for LoadFuture<T> {
+    fn drop(&mut self) {
+        let result = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            state.result.take()
+        };
+        drop(result);
+    }
+}
+
+impl<'a, T, I, P0> AudioLoader<'a, T, I, P0> {
+    /// Set path to file.
+    ///
+    /// The file replaces the buffer of a loader created with [`AudioLoader::from_memory`],
+    /// the reader of one created with [`AudioLoader::from_reader`] or the samples of one
+    /// created with [`AudioLoader::from_decoded`].
+    pub fn path<P1: AsRef<Path>>(self, path: P1) -> AudioLoader<'a, T, I, P1> {
+        AudioLoader {
+            path,
+            context: self.context,
+            device: self.device,
+            volume: self.volume,
+            match_device_rate: self.match_device_rate,
+            looping: self.looping,
+            autoplay: self.autoplay,
+            start: self.start,
+            end: self.end,
+            bus: self.bus,
+            memory: None,
+            reader: None,
+            generator: false,
+            decoded: None,
+            load_progress: self.load_progress,
+            on_end: self.on_end,
+            user_data: self.user_data,
+        }
+    }
+}
+
+impl<'a, T0, I, P> AudioLoader<'a, T0, I, P> {
+    /// Sets custom userdata
+    pub fn user_data<T1>(self, user_data: T1) -> AudioLoader<'a, T1, I, P> {
+        AudioLoader {
+            path: self.path,
+            context: self.context,
+            device: self.device,
+            volume: self.volume,
+            match_device_rate: self.match_device_rate,
+            looping: self.looping,
+            autoplay: self.autoplay,
+            start: self.start,
+            end: self.end,
+            bus: self.bus,
+            memory: self.memory,
+            reader: self.reader,
+            generator: self.generator,
+            decoded: self.decoded,
+            load_progress: self.load_progress,
+            on_end: self.on_end,
+            user_data,
+        }
+    }
+}
+
+impl<'a, T, P> AudioLoader<'a, T, void::Void, P> {
+    /// Sets closure to be run when the audio handle reaches the end.
+    pub fn on_end<F1: FnMut(&mut T) + Send>(self, on_end: F1) -> AudioLoader<'a, T, F1, P> {
+        AudioLoader {
+            path: self.path,
+            context: self.context,
+            device: self.device,
+            volume: self.volume,
+            match_device_rate: self.match_device_rate,
+            looping: self.looping,
+            autoplay: self.autoplay,
+            start: self.start,
+            end: self.end,
+            bus: self.bus,
+            memory: self.memory,
+            reader: self.reader,
+            generator: self.generator,
+            decoded: self.decoded,
+            load_progress: self.load_progress,
+            on_end: Some(on_end),
+            user_data: self.user_data,
+        }
+    }
+}
+
+struct ReferenceGain {
+    level: f32,
+    user_volume: Option<f32>,
+}
+
+struct InnerHandle<T> {
+    id: usize,
+    path: PathBuf,
+    context: Context,
+    reference: Mutex<ReferenceGain>,
+    //the volume to restore while muted
+    muted: Mutex<Option<f32>>,
+    user_data: RwLock<T>,
+    //decoded in place by the backend so it must live as long as the clip
+    #[allow(dead_code)]
+    memory: Option<Vec<u8>>,
+    #[allow(dead_code)]
+    reader: Option<Reader>,
+    #[allow(dead_code)]
+    decoded: Option<Arc<DecodedAudio>>,
+    #[allow(clippy::type_complexity)]
+    on_end: Mutex<Option<Box<dyn FnMut(&mut T) + Send>>>,
+    #[allow(clippy::type_complexity)]
+    on_loop: Mutex<Option<Box<dyn FnMut(&mut T, u64) + Send>>>,
+    //wraps of the loop since the clip was loaded or reset
+    loop_count: AtomicU64,
+    //keeps the clip alive until the fade out of fade_out_and_release ends
+    release: Mutex<Option<Arc<InnerHandle<T>>>>,
+    device_lost: Mutex<Option<Box<dyn FnMut() + Send>>>,
+    ended: EndSignal,
+    end_hook: EndHook,
+}
+
+impl<T> InnerHandle<T> {
+    fn on_end(&self) {
+        if let Some(closure) = &mut *self.on_end.lock().unwrap() {
+            let mut user_data = self.user_data.write().unwrap();
+            closure(&mut user_data);
+        }
+        //senders whose receiver was dropped are removed on the next end
+        self.context
+            .inner
+            .end_events
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.send(self.id).is_ok());
+        self.ended.notify();
+    }
+
+    fn on_loop(&self) {
+        let mut on_loop = self.on_loop.lock().unwrap();
+        let loops = self
+            .context
+            .with(|context| unsafe { takeLoops(self.id, context) });
+        for _ in 0..loops {
+            let count = self.loop_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(closure) = &mut *on_loop {
+                closure(&mut self.user_data.write().unwrap(), count);
+            }
+        }
+    }
+}
+
+/// Wakes everyone waiting for a clip to reach its end.
+#[derive(Default)]
+struct EndSignal {
+    state: Mutex<EndState>,
+    cv: Condvar,
+}
+
+#[derive(Default)]
+struct EndState {
+    //counts ends so a waiter can tell a new end from one that happened before it started
+    count: u64,
+    wakers: Vec<Waker>,
+}
+
+impl EndSignal {
+    fn notify(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.count += 1;
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+        self.cv.notify_all();
+    }
+
+    fn count(&self) -> u64 {
+        self.state.lock().unwrap().count
+    }
+}
+
+impl<T> Drop for InnerHandle<T> {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.context
+            .with(|context| unsafe { removeSound(id, context) });
+        free_id(id);
+        self.end_hook.progress.restart();
+        self.end_hook.loops.restart();
+    }
+}
+
+/// A handle that can be used to control audio playback.
+///
+/// Cloning a handle returns another handle to the same clip. The clip is removed
+/// once the last handle is dropped.
+pub struct AudioHandle<T> {
+    inner: Arc<InnerHandle<T>>,
+}
+
+impl<T> Clone for AudioHandle<T> {
+    fn clone(&self) -> Self {
+        AudioHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+//the user data is left out since it is not required to implement Debug
+impl<T> fmt::Debug for AudioHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioHandle")
+            .field("id", &self.inner.id)
+            .field("path", &self.inner.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle that does not keep its clip alive.
+///
+/// Created with [`AudioHandle::downgrade`]. The clip is still removed once the last
+/// [`AudioHandle`] is dropped, after which [`WeakAudioHandle::upgrade`] returns `None`.
+pub struct WeakAudioHandle<T> {
+    inner: Weak<InnerHandle<T>>,
+}
+
+impl<T> Clone for WeakAudioHandle<T> {
+    fn clone(&self) -> Self {
+        WeakAudioHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for WeakAudioHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakAudioHandle").finish_non_exhaustive()
+    }
+}
+
+impl<T> WeakAudioHandle<T> {
+    /// Returns a handle to the clip if it has not been removed yet.
+    pub fn upgrade(&self) -> Option<AudioHandle<T>> {
+        self.inner.upgrade().map(|inner| AudioHandle { inner })
+    }
+}
+
+impl<T> AudioHandle<T> {
+    /// Creates a handle to the clip that does not keep it alive.
+    pub fn downgrade(&self) -> WeakAudioHandle<T> {
+        WeakAudioHandle {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    fn with_clip<R: Default>(&self, f: impl FnOnce(usize, &AudioContext) -> R) -> R {
+        self.inner.context.with(|context| f(self.inner.id, context))
+    }
+
+    /// Runs a playback control and maps its status code to an error.
+    fn control(&self, f: impl FnOnce(usize, &AudioContext) -> i32) -> Result<(), AudioError> {
+        match self
+            .inner
+            .context
+            .with(|context| Some(f(self.inner.id, context)))
+        {
+            Some(0) => Ok(()),
+            Some(-2) => Err(AudioError::DeviceError),
+            Some(-4) => Err(AudioError::SeekError),
+            Some(code) => Err(AudioError::UnknownError(code)),
+            None => Err(AudioError::ContextError),
+        }
+    }
+
+    /// Starts playing audio.
+    ///
+    /// If the clip has reached its end it is restarted from the beginning, unless
+    /// [`AudioHandle::set_replay_on_play`] was disabled in which case nothing happens.
+    /// Returns [`AudioError::DeviceError`] if the output device could not be started.
+    pub fn play(&self) -> Result<(), AudioError> {
+        self.control(|id, context| unsafe { play(id, context) })
+    }
+
+    /// Keeps the output device running while the clip is not playing.
+    ///
+    /// Starting a device takes time that depends on the backend, so a primed clip
+    /// starts on the next buffer the device requests instead. This reduces the latency
+    /// of [`AudioHandle::play`] to at most one device period, but the device keeps
+    /// consuming resources while the clip is idle.
+    pub fn set_instant(&self, instant: bool) {
+        self.with_clip(|id, context| unsafe { setInstant(id, context, instant) });
+    }
+
+    /// Sets what [`AudioHandle::play`] does after the clip has reached its end.
+    ///
+    /// When true (the default) the clip restarts from the beginning.
+    /// When false `play` does nothing until [`AudioHandle::reset`] is called.
+    pub fn set_replay_on_play(&self, replay: bool) {
+        self.with_clip(|id, context| unsafe { setReplayOnPlay(id, context, replay) });
+    }
+
+    /// Sets whether the clip restarts from the beginning when it reaches its end.
+    ///
+    /// The clip wraps around without a gap and `on_end` is not called.
+    /// Turning looping off while the clip is playing lets the current pass finish normally.
+    pub fn set_looping(&self, looping: bool) {
+        self.with_clip(|id, context| unsafe { setLooping(id, context, looping) });
+    }
+
+    /// Checks if the clip is looping.
+    pub fn is_looping(&self) -> bool {
+        self.with_clip(|id, context| unsafe { isLooping(id, context) })
+    }
+
+    /// Repeats the part of the clip between `start` and `end`.
+    ///
+    /// When playback reaches `end` it jumps back to `start` without a gap. An `end` past the
+    /// end of the clip is clamped to it. Returns [`AudioError::InvalidRange`] if `end` is not
+    /// after `start`.
+    pub fn set_loop_region(&self, start: Duration, end: Duration) -> Result<(), AudioError> {
+        if end.is_zero() {
+            return Err(AudioError::InvalidRange);
+        }
+        let valid = self.with_clip(|id, context| unsafe {
+            setLoopRegion(
+                id,
+                context,
+                start.as_micros() as u64,
+                end.as_micros() as u64,
+            )
+        });
+        if valid {
+            Ok(())
+        } else {
+            Err(AudioError::InvalidRange)
+        }
+    }
+
+    /// Removes the loop region so playback continues to the end of the clip.
+    pub fn clear_loop_region(&self) {
+        self.with_clip(|id, context| unsafe { setLoopRegion(id, context, 0, 0) });
+    }
+
+    /// Ends the clip early once it stays below `threshold_db` for `min_duration`.
+    ///
+    /// Reaching the silence behaves like reaching the end of the clip, so `on_end` is called.
+    /// The level is measured on the decoded signal before the volume is applied.
+    /// Silence detection is disabled by default.
+    pub fn set_silence_detect(&self, threshold_db: f32, min_duration: Duration) {
+        self.with_clip(|id, context| unsafe {
+            setSilenceDetect(
+                id,
+                context,
+                10f32.powf(threshold_db / 20.0),
+                min_duration.as_millis() as u64,
+            )
+        });
+    }
+
+    /// Disables silence detection.
+    pub fn clear_silence_detect(&self) {
+        self.with_clip(|id, context| unsafe { setSilenceDetect(id, context, -1.0, 0) });
+    }
+
+    /// Pauses playback.
+    pub fn stop(&self) -> Result<(), AudioError> {
+        self.control(|id, context| unsafe { stop(id, context) })
+    }
+
+    /// Pauses playback until the returned guard is dropped.
+    ///
+    /// Playback is only resumed if the clip was playing when the guard was created.
+    pub fn pause_guard(&self) -> PauseGuard<'_, T> {
+        let was_playing = self.is_playing();
+        let _ = self.stop();
+        PauseGuard {
+            handle: self,
+            was_playing,
+        }
+    }
+
+    /// Resets to start of audio clip.
+    ///
+    /// The loop count passed to the closure of [`AudioHandle::set_on_loop`] starts over.
+    pub fn reset(&self) -> Result<(), AudioError> {
+        self.control(|id, context| unsafe { reset(id, context) })?;
+        self.with_clip(|id, context| unsafe { takeLoops(id, context) });
+        self.inner.loop_count.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Plays the clip from its start, whether it was playing, paused or stopped.
+    ///
+    /// A playing clip is rewound without stopping its device, so retriggering a sound
+    /// has no gap.
+    pub fn replay(&self) -> Result<(), AudioError> {
+        self.control(|id, context| unsafe { replay(id, context) })
+    }
+
+    /// Moves playback to `position`.
+    ///
+    /// Positions past the end of the clip are clamped to the end. A paused clip stays
+    /// paused at the new position.
+    ///
+    /// A playing clip is moved by the audio thread before its next block, so only a clip
+    /// that is not seekable at all is reported as an error then.
+    pub fn seek_to(&self, position: Duration) -> Result<(), AudioError> {
+        if self.with_clip(|id, context| unsafe { seek(id, context, position.as_micros() as u64) }) {
+            Ok(())
+        } else {
+            Err(AudioError::SeekError)
+        }
+    }
+
+    /// Checks if the source of the clip supports [`AudioHandle::seek_to`].
+    pub fn is_seekable(&self) -> bool {
+        self.with_clip(|id, context| unsafe { isSeekable(id, context) })
+    }
+
+    /// Returns the id of the clip, which is shared by all clones of the handle.
+    ///
+    /// Ids are unique among loaded clips but are reused once every handle to a clip
+    /// has been dropped. See [`Context::end_events`].
+    pub fn id(&self) -> usize {
+        self.inner.id
+    }
+
+    /// Returns the path used to create the handle.
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// Returns name of file used to create the handle.
+    pub fn name(&self) -> &str {
+        self.inner
+            .path
+            .file_name()
+            .unwrap_or_else(|| OsStr::new("Undefined"))
+            .to_str()
+            .unwrap_or("Undefined")
+    }
+
+    /// Sets volume.
+    ///
+    /// The volume is clamped to `0.0..=`[`MAX_VOLUME`] and NaN is treated as `0.0`.
+    /// While the clip is muted this only changes the volume that [`AudioHandle::unmute`] restores.
+    pub fn set_volume(&self, volume: f32) {
+        let volume = clamp_volume(volume);
+        if let Some(restore) = &mut *self.inner.muted.lock().unwrap() {
+            *restore = volume;
+            return;
+        }
+        self.with_clip(|id, context| unsafe { setVolume(id, context, volume) });
+    }
+
+    /// Silences the clip and remembers its volume for [`AudioHandle::unmute`].
+    ///
+    /// [`AudioHandle::volume`] returns 0 while muted. Muting a muted clip does nothing.
+    pub fn mute(&self) {
+        let mut muted = self.inner.muted.lock().unwrap();
+        if muted.is_none() {
+            *muted = Some(self.volume());
+            self.with_clip(|id, context| unsafe { setVolume(id, context, 0.0) });
+        }
+    }
+
+    /// Restores the volume the clip had when it was muted.
+    pub fn unmute(&self) {
+        if let Some(volume) = self.inner.muted.lock().unwrap().take() {
+            self.with_clip(|id, context| unsafe { setVolume(id, context, volume) });
+        }
+    }
+
+    /// Checks if the clip is muted.
+    pub fn is_muted(&self) -> bool {
+        self.inner.muted.lock().unwrap().is_some()
+    }
+
+    /// Returns volume.
+    pub fn volume(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getVolume(id, context) })
+    }
+
+    /// Sets volume in decibels.
+    ///
+    /// `0.0` is the unchanged level and negative infinity is silence.
+    pub fn set_volume_db(&self, db: f32) {
+        self.set_volume(10f32.powf(db / 20.0));
+    }
+
+    /// Returns volume in decibels.
+    ///
+    /// A volume of `0.0` is negative infinity.
+    pub fn volume_db(&self) -> f32 {
+        20.0 * self.volume().log10()
+    }
+
+    /// Ramps the volume from its current value to `target_volume` over `over`.
+    ///
+    /// The ramp follows `curve` and is applied sample by sample on the audio thread. While it
+    /// runs [`AudioHandle::volume`] reports the current point of the ramp, and
+    /// [`AudioHandle::set_volume`] cancels it. The target is clamped like the volume.
+    pub fn fade_to(&self, target_volume: f32, over: Duration, curve: FadeCurve) {
+        let target_volume = clamp_volume(target_volume);
+        self.with_clip(|id, context| unsafe {
+            fade(
+                id,
+                context,
+                -1f32,
+                target_volume,
+                over.as_micros() as u64,
+                false,
+                curve.to_raw(),
+            )
+        });
+    }
+
+    /// Starts playing with the volume ramping up from silence over `over`.
+    pub fn play_fade_in(&self, over: Duration, curve: FadeCurve) -> Result<(), AudioError> {
+        let volume = self.volume();
+        self.with_clip(|id, context| unsafe {
+            fade(
+                id,
+                context,
+                0f32,
+                volume,
+                over.as_micros() as u64,
+                false,
+                curve.to_raw(),
+            )
+        });
+        self.play()
+    }
+
+    /// Ramps the volume down to silence over `over` and pauses playback once it is reached.
+    ///
+    /// The volume is restored after pausing so the next [`AudioHandle::play`] is not silent.
+    /// Pausing the clip before the fade has finished cancels it and restores the volume too.
+    pub fn stop_fade_out(&self, over: Duration, curve: FadeCurve) {
+        self.with_clip(|id, context| unsafe {
+            fade(
+                id,
+                context,
+                -1f32,
+                0f32,
+                over.as_micros() as u64,
+                true,
+                curve.to_raw(),
+            )
+        });
+    }
+
+    /// Sets the reference volume used by [`AudioHandle::toggle_reference`].
+    ///
+    /// If the reference is currently active the new level is applied immediately.
+    pub fn set_reference_level(&self, level: f32) {
+        let mut reference = self.inner.reference.lock().unwrap();
+        reference.level = level;
+        if reference.user_volume.is_some() {
+            self.set_volume(level);
+        }
+    }
+
+    /// Switches between the user volume and the reference volume.
+    ///
+    /// Returns true if the reference volume is now active.
+    pub fn toggle_reference(&self) -> bool {
+        let mut reference = self.inner.reference.lock().unwrap();
+        match reference.user_volume.take() {
+            Some(volume) => {
+                self.set_volume(volume);
+                false
+            }
+            None => {
+                reference.user_volume = Some(self.volume());
+                self.set_volume(reference.level);
+                true
+            }
+        }
+    }
+
+    /// Checks if the reference volume is currently active.
+    pub fn is_reference_active(&self) -> bool {
+        self.inner.reference.lock().unwrap().user_volume.is_some()
+    }
+
+    /// Plays the decoded samples at `hz` instead of the native sample rate of the file.
+    ///
+    /// The samples themselves are not processed, so speed and pitch change together by
+    /// `hz / native rate`, like playing a tape at the wrong speed. [`AudioHandle::duration`]
+    /// and [`AudioHandle::position`] stay in the time of the file, so a clip played at
+    /// twice its rate still reports its native length. Passing `0` restores the native rate,
+    /// any other rate is clamped to `8000..=384000` hz, the range the backend supports.
+    /// Volume, position and playback state are kept, and a rate the device can not be
+    /// opened with leaves the previous rate in place.
+    ///
+    /// The playback rate is the one setting behind both this and [`AudioHandle::set_speed`],
+    /// which sets it relative to the native rate, so the last call of either wins. To change
+    /// the tempo without the pitch use [`AudioHandle::set_tempo`] instead.
+    pub fn set_playback_rate(&self, hz: u32) {
+        self.with_clip(|id, context| unsafe { setPlaybackRate(id, context, hz) });
+    }
+
+    /// Returns the rate in hz at which decoded samples are played.
+    pub fn playback_rate(&self) -> u32 {
+        self.with_clip(|id, context| unsafe { getPlaybackRate(id, context) })
+    }
+
+    /// Sets the playback speed where `1.0` is normal speed.
+    ///
+    /// This sets the playback rate of [`AudioHandle::set_playback_rate`] to `speed` times the
+    /// native rate, so pitch changes together with tempo, a speed of `0.5` plays an octave
+    /// lower at half the tempo. The resulting rate is clamped like the playback rate, and
+    /// speeds that are not finite or not greater than zero are ignored. The playback
+    /// position is kept.
+    pub fn set_speed(&self, speed: f32) {
+        if speed > 0f32 && speed.is_finite() {
+            self.with_clip(|id, context| unsafe { setSpeed(id, context, speed) });
+        }
+    }
+
+    /// Returns the playback speed.
+    pub fn speed(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getSpeed(id, context) })
+    }
+
+    /// Sets the tempo where `1.0` is normal tempo, without changing the pitch.
+    ///
+    /// Unlike [`AudioHandle::set_speed`] the audio is time stretched, so speech at `1.5`
+    /// still sounds natural. The stretch works on 40ms windows which costs some CPU time and
+    /// makes the position run up to one window ahead of what is heard, and tempos far from
+    /// `1.0` can sound slightly phasey. At `1.0` the stretcher is bypassed entirely.
+    /// Tempos are clamped to `0.25..=4.0` and can be combined with [`AudioHandle::set_speed`].
+    pub fn set_tempo(&self, tempo: f32) {
+        self.with_clip(|id, context| unsafe { setTempo(id, context, tempo) });
+    }
+
+    /// Returns the tempo.
+    pub fn tempo(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getTempo(id, context) })
+    }
+
+    /// Pans the clip between the left (`-1.0`) and right (`1.0`) speaker.
+    ///
+    /// `0.0` is centered and values outside `-1.0..=1.0` are clamped. Mono files are
+    /// played on both channels so they can be panned as well. Clips with more than two
+    /// channels are not panned.
+    pub fn set_pan(&self, pan: f32) {
+        self.with_clip(|id, context| unsafe { setPan(id, context, pan) });
+    }
+
+    /// Returns the pan of the clip.
+    pub fn pan(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getPan(id, context) })
+    }
+
+    /// Makes the clip skip the custom node of its context, see [`Context::insert_custom_node`].
+    pub fn set_custom_node_bypass(&self, bypass: bool) {
+        self.with_clip(|id, context| unsafe { setProcessorBypass(id, context, bypass) });
+    }
+
+    /// Checks if the clip skips the custom node of its context.
+    pub fn is_custom_node_bypassed(&self) -> bool {
+        self.with_clip(|id, context| unsafe { getProcessorBypass(id, context) })
+    }
+
+    /// Resets every effect on the clip so it plays its dry signal.
+    ///
+    /// The pan and tempo go back to their defaults, the clip stops bypassing the custom node
+    /// of its context, and stops being positioned in 3D with the attenuation model back at
+    /// its default. A fade in progress is held at the volume it reached, and one started by
+    /// [`AudioHandle::stop_fade_out`] goes back to the volume before it without stopping the
+    /// clip. The volume, speed, looping, loop region, silence detection and playback state
+    /// are kept. Does nothing on a clip without effects.
+    pub fn clear_all_effects(&self) {
+        self.with_clip(|id, context| unsafe { clearEffects(id, context) });
+    }
+
+    /// Places the clip at `x`, `y`, `z` relative to the listener of its context.
+    ///
+    /// From then on the clip is attenuated by its distance to the listener, see
+    /// [`AudioHandle::set_attenuation_model`], and stereo clips are panned towards the side
+    /// of the listener it is on. This is applied on top of [`AudioHandle::set_pan`].
+    /// See [`Context::set_listener_position`] for the coordinate system.
+    pub fn set_position_3d(&self, x: f32, y: f32, z: f32) {
+        self.with_clip(|id, context| unsafe { setPosition3d(id, context, x, y, z) });
+    }
+
+    /// Stops positioning the clip so it plays without attenuation or spatial panning.
+    pub fn clear_position_3d(&self) {
+        self.with_clip(|id, context| unsafe { clearPosition3d(id, context) });
+    }
+
+    /// Sets how the volume of a positioned clip falls off with distance.
+    ///
+    /// Defaults to [`AttenuationModel::Exponential`].
+    pub fn set_attenuation_model(&self, model: AttenuationModel) {
+        self.with_clip(|id, context| unsafe { setAttenuation(id, context, model.to_raw()) });
+    }
+
+    /// Returns how the volume of a positioned clip falls off with distance.
+    pub fn attenuation_model(&self) -> AttenuationModel {
+        AttenuationModel::from_raw(
+            self.with_clip(|id, context| unsafe { getAttenuation(id, context) }),
+        )
+    }
+
+    /// Checks if the clip is resampled to play on its output device.
+    ///
+    /// This covers both the conversion of the file to the rate of the context, see
+    /// [`ContextBuilder::sample_rate`], and the conversion to the rate of the device.
+    pub fn is_resampling(&self) -> bool {
+        self.with_clip(|id, context| unsafe { isResampling(id, context) })
+    }
+
+    /// Returns the playback state of the handle.
+    pub fn state(&self) -> PlaybackState {
+        PlaybackState::from_raw(self.with_clip(|id, context| unsafe { getState(id, context) }))
+    }
+
+    /// Checks if the handle is currently playing
+    pub fn is_playing(&self) -> bool {
+        self.state() == PlaybackState::Playing
+    }
+
+    /// Blocks the calling thread until the clip reaches its end.
+    ///
+    /// Returns immediately if the clip is not playing and also returns once it is paused.
+    /// A looping clip never reaches its end, so this never returns while it keeps playing.
+    pub fn wait(&self) {
+        let signal = &self.inner.ended;
+        let mut state = signal.state.lock().unwrap();
+        let start = state.count;
+        //pausing does not notify so the state is checked again every so often
+        while state.count == start && self.is_playing() {
+            state = signal.cv.wait_timeout(state, WAIT_POLL).unwrap().0;
+        }
+    }
+
+    /// Returns a future that resolves once the clip reaches its end.
+    ///
+    /// Resolves immediately if the clip is not playing. Unlike [`AudioHandle::wait`] a clip
+    /// that is paused while the future is pending does not resolve it.
+    pub fn finished(&self) -> Finished<'_, T> {
+        Finished {
+            handle: self,
+            start: self.inner.ended.count(),
+        }
+    }
+
+    /// Checks if the handle is playing and its output can be heard.
+    ///
+    /// This is a heuristic based on the peak of the most recently played block
+    /// multiplied by the volume, which has to be above -60 dB.
+    pub fn is_audible(&self) -> bool {
+        self.with_clip(|id, context| unsafe { isAudible(id, context, 0.001) })
+    }
+
+    /// Returns the peak level of the most recently played block before effects and volume.
+    pub fn pre_fader_level(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getPreFaderLevel(id, context) })
+    }
+
+    /// Returns the peak level of the most recently played block after effects and volume.
+    pub fn post_fader_level(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getPostFaderLevel(id, context) })
+    }
+
+    /// Returns the peak amplitude of the most recently played block in the range `0.0..=1.0`.
+    ///
+    /// The level is measured after effects and volume and is cheap enough to read every frame.
+    pub fn peak_level(&self) -> f32 {
+        self.post_fader_level().clamp(0.0, 1.0)
+    }
+
+    /// Returns the RMS amplitude of the most recently played block in the range `0.0..=1.0`.
+    ///
+    /// Like [`AudioHandle::peak_level`] this reflects the output after effects and volume.
+    pub fn rms_level(&self) -> f32 {
+        self.with_clip(|id, context| unsafe { getRmsLevel(id, context) })
+            .clamp(0.0, 1.0)
+    }
+
+    /// Returns [`AudioHandle::pre_fader_level`] in decibels.
+    pub fn pre_fader_level_db(&self) -> f32 {
+        20.0 * self.pre_fader_level().log10()
+    }
+
+    /// Returns [`AudioHandle::post_fader_level`] in decibels.
+    pub fn post_fader_level_db(&self) -> f32 {
+        20.0 * self.post_fader_level().log10()
+    }
+
+    /// Checks if the handle is currently paused
+    ///
+    /// This is true whenever the handle is not playing, see [`AudioHandle::state`] to
+    /// tell a paused clip from a stopped one.
+    pub fn is_paused(&self) -> bool {
+        self.state() != PlaybackState::Playing
+    }
+
+    /// Gets duration of audio handle
+    ///
+    /// The duration is measured in the time of the file like [`AudioHandle::position`], so it
+    /// does not change with [`AudioHandle::set_speed`] or [`AudioHandle::set_playback_rate`].
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.with_clip(|id, context| unsafe { getDuration(id, context) }))
+    }
+
+    /// Gets the current playback position of the audio handle.
+    ///
+    /// The position does not move while the clip is paused and is zero after [`AudioHandle::reset`].
+    pub fn position(&self) -> Duration {
+        Duration::from_micros(self.with_clip(|id, context| unsafe { getPosition(id, context) }))
+    }
+
+    /// Returns the codec that was used to decode the file.
+    pub fn codec(&self) -> Codec {
+        match self.with_clip(|id, context| unsafe { getCodec(id, context) }) {
+            1 => Codec::Wav,
+            2 => Codec::Flac,
+            3 => Codec::Mp3,
+            4 => Codec::Vorbis,
+            _ => Codec::Unknown,
+        }
+    }
+
+    /// Returns the native properties of the file.
+    ///
+    /// These are the values stored in the file, not the ones of the output device.
+    pub fn info(&self) -> AudioInfo {
+        let info = self.with_clip(|id, context| unsafe { getInfo(id, context) });
+        AudioInfo {
+            sample_rate: info.sample_rate,
+            channels: info.channels as u16,
+            format: match info.format {
+                1 => SampleFormat::U8,
+                2 => SampleFormat::S16,
+                3 => SampleFormat::S24,
+                4 => SampleFormat::S32,
+                5 => SampleFormat::F32,
+                _ => SampleFormat::Unknown,
+            },
+        }
+    }
+
+    /// Returns the native sample rate of the file in hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.info().sample_rate
+    }
+
+    /// Returns the number of channels in the file.
+    pub fn channels(&self) -> u16 {
+        self.info().channels
+    }
+
+    /// Returns the native sample format of the file.
+    pub fn format(&self) -> SampleFormat {
+        self.info().format
+    }
+
+    /// Returns an iterator over the decoded blocks of audio as they are played.
+    ///
+    /// Blocks are interleaved `f32` samples in the clip's channel layout. The iterator blocks
+    /// until the next block is played and ends when the clip reaches its end or is dropped.
+    /// Only one stream is active per handle, creating a new one ends the previous one.
+    /// The audio thread never waits for the stream, if it is not consumed fast enough new
+    /// blocks are discarded until there is room again.
+    pub fn block_stream(&self) -> BlockStream {
+        let tap = self.with_clip(|id, context| unsafe { Some(openBlockTap(id, context)) });
+        BlockStream {
+            tap: tap.unwrap_or(std::ptr::null_mut()),
+        }
+    }
+
+    /// Returns a consistent snapshot of the handle's state.
+    pub fn snapshot(&self) -> HandleSnapshot {
+        let snapshot = self.with_clip(|id, context| unsafe { getSnapshot(id, context) });
+        HandleSnapshot {
+            path: self.inner.path.clone(),
+            volume: snapshot.volume,
+            duration: Duration::from_millis(snapshot.duration),
+            position: Duration::from_micros(snapshot.position),
+            state: PlaybackState::from_raw(snapshot.state),
+            pan: snapshot.pan,
+            speed: snapshot.speed,
+            looping: snapshot.looping,
         }
     }
 
+    /// Sets playback device
+    pub fn set_output_device(&self, device: &Device) {
+        self.with_clip(|id, context| unsafe { setAudioDevice(id, context, &device.device) });
+    }
+
+    /// Returns the output device the clip is playing on.
+    ///
+    /// This follows [`AudioHandle::set_output_device`] and [`Context::set_output_device`].
+    /// If the device is no longer connected its name is "Undefined".
+    pub fn current_output_device(&self) -> Device {
+        let mut device = AudioDevice::default();
+        self.with_clip(|id, context| unsafe { getClipDevice(id, context, &mut device) });
+        let context = &self.inner.context;
+        output_devices(context.clone())
+            .find(|output| output.device.id == device.id)
+            .unwrap_or_else(|| Device::new(device, false, context.clone()))
+    }
+
+    /// Copies the settings of `other` onto this handle.
+    ///
+    /// This copies the volume and whether the clip is muted, the playback rate relative to
+    /// the native rate of each file, the tempo, pan, custom node bypass, the 3D position and
+    /// attenuation model, looping, the loop region, the replay on play behaviour, silence
+    /// detection and the reference level. A loop region that does not fit into this clip is
+    /// cleared. The playback position and state and fades in progress are not copied.
+    pub fn copy_settings_from<U>(&self, other: &AudioHandle<U>) {
+        self.with_clip(|id, context| {
+            other.with_clip(|other_id, other_context| unsafe {
+                copySettings(id, context, other_id, other_context)
+            })
+        });
+        //a muted clip is at volume 0, so the volume unmute restores is carried over here
+        let muted = *other.inner.muted.lock().unwrap();
+        *self.inner.muted.lock().unwrap() = muted;
+        let level = other.inner.reference.lock().unwrap().level;
+        self.inner.reference.lock().unwrap().level = level;
+    }
+
+    /// Sets userdata.
+    pub fn set_user_data(&mut self, data: T) {
+        *self.inner.user_data.write().unwrap() = data;
+    }
+
+    /// Modifes userdata using closure.
+    pub fn modify_user_data<I: FnMut(&mut T)>(&self, mut closure: I) {
+        closure(&mut self.inner.user_data.write().unwrap());
+    }
+
+    /// Replaces the closure that is run when the clip reaches its end.
+    ///
+    /// Must not be called from inside the `on_end` closure itself.
+    pub fn set_on_end<F: FnMut(&mut T) + Send + 'static>(&self, on_end: F) {
+        *self.inner.on_end.lock().unwrap() = Some(Box::new(on_end));
+    }
+
+    /// Sets a closure that is run when the device of the clip disappears during playback.
+    ///
+    /// The clip is moved to the default output device of the system before the closure runs,
+    /// keeping its position, volume and playback state. If there is no output device left
+    /// the clip stays silent and [`AudioHandle::play`] returns an error.
+    pub fn set_on_device_lost<F: FnMut() + Send + 'static>(&self, on_device_lost: F) {
+        *self.inner.device_lost.lock().unwrap() = Some(Box::new(on_device_lost));
+    }
+
+    /// Removes the closure set by [`AudioHandle::set_on_device_lost`].
+    ///
+    /// Clips still move to the default device when their device disappears.
+    pub fn clear_on_device_lost(&self) {
+        *self.inner.device_lost.lock().unwrap() = None;
+    }
+
+    /// Removes the closure that is run when the clip reaches its end.
+    ///
+    /// Must not be called from inside the `on_end` closure itself.
+    pub fn clear_on_end(&self) {
+        *self.inner.on_end.lock().unwrap() = None;
+    }
+}
+
+/// The controls of an [`AudioHandle`] that do not depend on its user data.
+///
+/// Handles with different user data types can be stored together as `Box<dyn Playable>`.
+/// Every method forwards to the method of the same name on [`AudioHandle`].
+pub trait Playable: Send + Sync {
+    /// Returns the id of the clip.
+    fn id(&self) -> usize;
     /// Returns the path used to create the handle.
-    pub fn path(&self) -> &Path {
-        &self.inner.path
+    fn path(&self) -> &Path;
+    /// Starts or resumes playback.
+    fn play(&self) -> Result<(), AudioError>;
+    /// Pauses playback.
+    fn stop(&self) -> Result<(), AudioError>;
+    /// Resets to start of audio clip.
+    fn reset(&self) -> Result<(), AudioError>;
+    /// Moves playback to `position` from the start of the clip.
+    fn seek_to(&self, position: Duration) -> Result<(), AudioError>;
+    /// Sets the volume.
+    fn set_volume(&self, volume: f32);
+    /// Returns the volume.
+    fn volume(&self) -> f32;
+    /// Sets if the clip starts over when it reaches its end.
+    fn set_looping(&self, looping: bool);
+    /// Returns if the clip starts over when it reaches its end.
+    fn is_looping(&self) -> bool;
+    /// Returns the playback state.
+    fn state(&self) -> PlaybackState;
+    /// Returns if the clip is playing.
+    fn is_playing(&self) -> bool;
+    /// Returns the length of the clip.
+    fn duration(&self) -> Duration;
+    /// Returns the playback position.
+    fn position(&self) -> Duration;
+}
+
+impl<T: Send + Sync> Playable for AudioHandle<T> {
+    fn id(&self) -> usize {
+        AudioHandle::id(self)
     }
 
-    /// Returns name of file used to create the handle.
-    pub fn name(&self) -> &str {
-        self.inner
-            .path
-            .file_name()
-            .unwrap_or_else(|| OsStr::new("Undefined"))
-            .to_str()
-            .unwrap_or("Undefined")
+    fn path(&self) -> &Path {
+        AudioHandle::path(self)
     }
 
-    /// Sets volume.
-    pub fn set_volume(&self, volume: f32) {
+    fn play(&self) -> Result<(), AudioError> {
+        AudioHandle::play(self)
+    }
+
+    fn stop(&self) -> Result<(), AudioError> {
+        AudioHandle::stop(self)
+    }
+
+    fn reset(&self) -> Result<(), AudioError> {
+        AudioHandle::reset(self)
+    }
+
+    fn seek_to(&self, position: Duration) -> Result<(), AudioError> {
+        AudioHandle::seek_to(self, position)
+    }
+
+    fn set_volume(&self, volume: f32) {
+        AudioHandle::set_volume(self, volume)
+    }
+
+    fn volume(&self) -> f32 {
+        AudioHandle::volume(self)
+    }
+
+    fn set_looping(&self, looping: bool) {
+        AudioHandle::set_looping(self, looping)
+    }
+
+    fn is_looping(&self) -> bool {
+        AudioHandle::is_looping(self)
+    }
+
+    fn state(&self) -> PlaybackState {
+        AudioHandle::state(self)
+    }
+
+    fn is_playing(&self) -> bool {
+        AudioHandle::is_playing(self)
+    }
+
+    fn duration(&self) -> Duration {
+        AudioHandle::duration(self)
+    }
+
+    fn position(&self) -> Duration {
+        AudioHandle::position(self)
+    }
+}
+
+/// Fades `from` out and `to` in over the same duration.
+///
+/// `to` is started from silence and ramps up to its current volume while `from` ramps
+/// down and pauses once it is silent. `curve` picks the shape of both ramps, see
+/// [`AudioHandle::play_fade_in`] and [`AudioHandle::stop_fade_out`].
+/// Returns [`AudioError::ContextError`] if the handles belong to different contexts.
+pub fn crossfade<A, B>(
+    from: &AudioHandle<A>,
+    to: &AudioHandle<B>,
+    over: Duration,
+    curve: CrossfadeCurve,
+) -> Result<(), AudioError> {
+    if !Arc::ptr_eq(&from.inner.context.inner, &to.inner.context.inner) {
+        return Err(AudioError::ContextError);
+    }
+    to.play_fade_in(over, curve.fade_curve())?;
+    from.stop_fade_out(over, curve.fade_curve());
+    Ok(())
+}
+
+/// How often [`AudioHandle::wait`] checks if the clip was paused.
+const WAIT_POLL: Duration = Duration::from_millis(50);
+
+/// A future returned by [`AudioHandle::finished`].
+pub struct Finished<'a, T> {
+    handle: &'a AudioHandle<T>,
+    start: u64,
+}
+
+impl<'a, T> Future for Finished<'a, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.handle.inner.ended.state.lock().unwrap();
+        if state.count != self.start || !self.handle.is_playing() {
+            return Poll::Ready(());
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// A guard that resumes playback of a paused clip when dropped.
+pub struct PauseGuard<'a, T> {
+    handle: &'a AudioHandle<T>,
+    was_playing: bool,
+}
+
+impl<'a, T> Drop for PauseGuard<'a, T> {
+    fn drop(&mut self) {
+        if self.was_playing {
+            let _ = self.handle.play();
+        }
+    }
+}
+
+/// An iterator over decoded blocks of a playing clip.
+pub struct BlockStream {
+    tap: *mut c_void,
+}
+
+unsafe impl Send for BlockStream {}
+
+impl Iterator for BlockStream {
+    type Item = Vec<f32>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.tap.is_null() {
+            return None;
+        }
+        let mut block: Vec<f32> = Vec::new();
+        loop {
+            let len = unsafe { nextBlock(self.tap, block.as_mut_ptr(), block.capacity()) };
+            if len < 0 {
+                return None;
+            }
+            let len = len as usize;
+            if len <= block.capacity() {
+                unsafe { block.set_len(len) };
+                return Some(block);
+            }
+            block.reserve(len);
+        }
+    }
+}
+
+impl Drop for BlockStream {
+    fn drop(&mut self) {
         unsafe {
-            setVolume(self.inner.id, &self.inner.context.inner.context, volume);
+            closeBlockTap(self.tap);
         }
     }
+}
 
-    /// Returns volume.
-    pub fn volume(&self) -> f32 {
-        unsafe { getVolume(self.inner.id, &self.inner.context.inner.context) }
+/// Records audio from an input device.
+///
+/// Recorded audio is buffered as interleaved `f32` samples in the channel layout of
+/// the device until it is taken with [`Recorder::take`].
+pub struct Recorder {
+    recorder: *mut c_void,
+    context: Context,
+}
+
+unsafe impl Send for Recorder {}
+
+impl Recorder {
+    /// Creates a recorder that captures from the default input device.
+    pub fn new(context: Context) -> Result<Self, AudioError> {
+        Self::open(context, std::ptr::null())
     }
 
-    /// Checks if the handle is currently playing
-    pub fn is_playing(&self) -> bool {
-        unsafe { isPlaying(self.inner.id, &self.inner.context.inner.context) }
+    /// Creates a recorder that captures from `device`.
+    ///
+    /// `device` should be one of the devices returned by [`input_devices`].
+    pub fn with_device(device: &Device) -> Result<Self, AudioError> {
+        Self::open(device.context.clone(), &device.device)
     }
 
-    /// Checks if the handle is currently paused
-    pub fn is_paused(&self) -> bool {
-        unsafe { !isPlaying(self.inner.id, &self.inner.context.inner.context) }
+    fn open(context: Context, device: *const AudioDevice) -> Result<Self, AudioError> {
+        let recorder = context.with(|inner| unsafe { Some(openRecorder(inner, device)) });
+        match recorder {
+            Some(recorder) if !recorder.is_null() => Ok(Recorder { recorder, context }),
+            _ => Err(AudioError::DeviceError),
+        }
     }
 
-    /// Gets duration of audio handle
-    pub fn duration(&self) -> Duration {
-        unsafe {
-            Duration::from_millis(getDuration(
-                self.inner.id,
-                &self.inner.context.inner.context,
-            ))
+    /// Starts recording.
+    pub fn start(&self) -> Result<(), AudioError> {
+        if self
+            .context
+            .with(|_| unsafe { startRecorder(self.recorder) })
+        {
+            Ok(())
+        } else {
+            Err(AudioError::DeviceError)
         }
     }
 
-    /// Sets playback device
-    pub fn set_output_device(&self, device: &Device) {
+    /// Stops recording. Samples recorded so far are kept.
+    pub fn stop(&self) {
+        self.context
+            .with(|_| unsafe { stopRecorder(self.recorder) });
+    }
+
+    /// Checks if the recorder is currently recording.
+    pub fn is_recording(&self) -> bool {
+        self.context.with(|_| unsafe { isRecording(self.recorder) })
+    }
+
+    /// Returns the number of interleaved channels in the recorded samples.
+    pub fn channels(&self) -> u16 {
+        self.context
+            .with(|_| unsafe { getRecorderChannels(self.recorder) }) as u16
+    }
+
+    /// Returns the sample rate of the recorded samples in hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.context
+            .with(|_| unsafe { getRecorderSampleRate(self.recorder) })
+    }
+
+    /// Takes all samples recorded since the last call.
+    pub fn take(&self) -> Vec<f32> {
+        self.context.with(|_| unsafe {
+            let capacity = getRecordedLength(self.recorder);
+            let mut samples: Vec<f32> = Vec::with_capacity(capacity);
+            let len = takeRecorded(self.recorder, samples.as_mut_ptr(), capacity);
+            samples.set_len(len);
+            samples
+        })
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.context
+            .with(|context| unsafe { closeRecorder(context, self.recorder) });
+    }
+}
+
+/// A group of clips that share a volume.
+///
+/// The volume of a group is applied on top of the volume of each clip routed to it, so a
+/// clip is played at its own volume times the volume of its group times the master volume
+/// of the context, see [`Context::set_master_volume`].
+#[derive(Clone)]
+pub struct SoundGroup {
+    inner: Arc<InnerGroup>,
+}
+
+struct InnerGroup {
+    group: *mut c_void,
+}
+
+unsafe impl Send for InnerGroup {}
+unsafe impl Sync for InnerGroup {}
+
+impl SoundGroup {
+    fn new() -> Self {
+        SoundGroup {
+            inner: Arc::new(InnerGroup {
+                group: unsafe { createGroup() },
+            }),
+        }
+    }
+
+    /// Sets the volume of the group.
+    ///
+    /// The volume is clamped like [`AudioHandle::set_volume`].
+    pub fn set_volume(&self, volume: f32) {
+        unsafe { setGroupVolume(self.inner.group, clamp_volume(volume)) }
+    }
+
+    /// Gets the volume of the group.
+    pub fn volume(&self) -> f32 {
+        unsafe { getGroupVolume(self.inner.group) }
+    }
+
+    /// Silences every clip in the group without changing the volume of the group.
+    pub fn mute(&self) {
+        unsafe { setGroupMuted(self.inner.group, true) }
+    }
+
+    /// Restores the volume of the group after [`SoundGroup::mute`].
+    pub fn unmute(&self) {
+        unsafe { setGroupMuted(self.inner.group, false) }
+    }
+
+    /// Returns if the group is muted.
+    pub fn is_muted(&self) -> bool {
+        unsafe { isGroupMuted(self.inner.group) }
+    }
+
+    /// Lowers the group by `amount_db` while any clip routed to `trigger` is heard.
+    ///
+    /// A clip counts as heard while its output through `trigger` is above -60 dB, so a
+    /// muted trigger does not duck. The group is lowered over `attack` and comes back over
+    /// `release` once the trigger has been silent for 50 ms. A group follows one trigger,
+    /// calling this again replaces it, and a group can not duck under itself.
+    pub fn duck_under(
+        &self,
+        trigger: &SoundGroup,
+        amount_db: f32,
+        attack: Duration,
+        release: Duration,
+    ) {
+        if Arc::ptr_eq(&self.inner, &trigger.inner) {
+            return;
+        }
         unsafe {
-            setAudioDevice(
-                self.inner.id,
-                &self.inner.context.inner.context,
-                &device.device,
+            setGroupDuck(
+                self.inner.group,
+                trigger.inner.group,
+                10f32.powf(-amount_db.abs() / 20.0),
+                attack.as_micros() as u64,
+                release.as_micros() as u64,
             )
         }
     }
 
-    /// Sets userdata.
-    pub fn set_user_data(&mut self, data: T) {
+    /// Stops ducking under the trigger set by [`SoundGroup::duck_under`] and restores the
+    /// volume right away.
+    pub fn clear_duck(&self) {
+        unsafe { setGroupDuck(self.inner.group, std::ptr::null_mut(), 1f32, 0, 0) }
+    }
+}
+
+/// Where a loader routes its clip.
+#[derive(Clone)]
+enum Route {
+    //resolved against the context of the loader when it is loaded
+    Bus(String),
+    Group(SoundGroup),
+}
+
+impl Drop for InnerGroup {
+    fn drop(&mut self) {
         unsafe {
-            *Arc::get_mut_unchecked(&mut *self.inner.user_data.write().unwrap()) = data;
+            releaseGroup(self.group);
         }
     }
+}
 
-    /// Modifes userdata using closure.
-    pub fn modify_user_data<I: FnMut(&mut T)>(&self, mut closure: I) {
-        let mut refrence = self.inner.user_data.write().unwrap();
-        unsafe {
-            let user_data = Arc::get_mut_unchecked(&mut refrence);
-            closure(user_data);
+impl<T: 'static + Send + Sync> AudioHandle<T> {
+    /// Stops calling the progress closure set by [`AudioHandle::set_on_progress`].
+    pub fn clear_on_progress(&self) {
+        self.with_clip(|id, context| unsafe { setProgressInterval(id, context, 0) });
+        self.inner.end_hook.progress.restart();
+    }
+
+    /// Calls `on_progress` with the current position roughly every `interval` of playback.
+    ///
+    /// The closure runs on a separate thread, so it may block or update a UI, and it is
+    /// not called while the clip is paused or after it has finished. Replaces any previous
+    /// progress closure.
+    pub fn set_on_progress<F: FnMut(Duration) + Send + 'static>(
+        &self,
+        interval: Duration,
+        mut on_progress: F,
+    ) {
+        let signal = self.inner.end_hook.progress.clone();
+        let generation = signal.restart();
+        let weak = Arc::downgrade(&self.inner);
+        std::thread::spawn(move || {
+            while signal.wait(generation) {
+                let handle = match weak.upgrade() {
+                    Some(inner) => AudioHandle { inner },
+                    None => return,
+                };
+                //the report may arrive after the clip was paused or reached its end
+                if handle.is_playing() {
+                    on_progress(handle.position());
+                }
+            }
+        });
+        self.with_clip(|id, context| unsafe {
+            setProgressInterval(id, context, interval.as_micros() as u64)
+        });
+    }
+
+    /// Calls `on_loop` with the user data and the number of loops played each time a
+    /// looping clip or its loop region wraps around.
+    ///
+    /// The count starts at 1 for the first wrap and goes back to zero on
+    /// [`AudioHandle::reset`]. Wraps while no closure is set are counted but not
+    /// reported. The closure runs on a separate thread, or inside [`Context::advance`] on an
+    /// offline context, and replaces any previous loop closure.
+    pub fn set_on_loop<F: FnMut(&mut T, u64) + Send + 'static>(&self, on_loop: F) {
+        let signal = self.inner.end_hook.loops.clone();
+        let generation = signal.restart();
+        self.inner.on_loop();
+        *self.inner.on_loop.lock().unwrap() = Some(Box::new(on_loop));
+        if self.inner.context.inner.context.offline {
+            return;
+        }
+        let weak = Arc::downgrade(&self.inner);
+        std::thread::spawn(move || {
+            while signal.wait(generation) {
+                match weak.upgrade() {
+                    Some(inner) => inner.on_loop(),
+                    None => return,
+                }
+            }
+        });
+    }
+
+    /// Stops calling the loop closure set by [`AudioHandle::set_on_loop`].
+    ///
+    /// Wraps are still counted.
+    pub fn clear_on_loop(&self) {
+        self.inner.end_hook.loops.restart();
+        *self.inner.on_loop.lock().unwrap() = None;
+    }
+
+    /// Fades the clip out over `duration` and releases it afterwards.
+    ///
+    /// The clip is kept alive until the fade out ends, so it is not cut off the way it
+    /// would be by dropping the handle. The fade out ends when it stops the clip, when the
+    /// clip reaches its end, or when another handle to the clip stops it, changes its volume
+    /// or starts another fade. A clip that is not playing is released right away.
+    pub fn fade_out_and_release(self, duration: Duration) {
+        *self.inner.release.lock().unwrap() = Some(self.inner.clone());
+        self.stop_fade_out(duration, FadeCurve::default());
+        //a fade too short for a single frame stops the clip without ending on the audio thread
+        if !self.is_playing() {
+            let release = self.inner.release.lock().unwrap().take();
+            drop(release);
+        }
+    }
+}
+
+/// An ordered list of clips that advances to the next clip when one ends.
+///
+/// Every clip is loaded with an `on_end` callback owned by the playlist, so advancing
+/// happens on the backend's end notification without polling. The user data of each
+/// clip is its index in the playlist. Dropping the playlist stops and releases all clips.
+pub struct Playlist {
+    inner: Arc<InnerPlaylist>,
+}
+
+struct InnerPlaylist {
+    state: Mutex<PlaylistState>,
+}
+
+struct PlaylistState {
+    clips: Vec<AudioHandle<usize>>,
+    current: usize,
+    repeat: Repeat,
+}
+
+impl InnerPlaylist {
+    fn on_end(&self, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        //the track was skipped while it was ending, or the playlist dropped its clips
+        if state.current != index || state.clips.get(index).is_none() {
+            return;
+        }
+        let next = match state.repeat {
+            Repeat::One => index,
+            Repeat::All => (index + 1) % state.clips.len(),
+            Repeat::Off if index + 1 < state.clips.len() => index + 1,
+            Repeat::Off => return,
+        };
+        state.current = next;
+        let _ = state.clips[next].play();
+    }
+}
+
+impl Playlist {
+    /// Creates an empty playlist.
+    pub fn new() -> Playlist {
+        Playlist {
+            inner: Arc::new(InnerPlaylist {
+                state: Mutex::new(PlaylistState {
+                    clips: Vec::new(),
+                    current: 0,
+                    repeat: Repeat::Off,
+                }),
+            }),
+        }
+    }
+
+    /// Loads a clip and appends it to the end of the playlist.
+    ///
+    /// The loader keeps all of its settings, but its user data and `on_end` callback are
+    /// replaced by the playlist.
+    pub fn push<P: AsRef<Path>>(
+        &self,
+        loader: AudioLoader<'_, (), void::Void, P>,
+    ) -> Result<(), AudioError> {
+        let mut state = self.inner.state.lock().unwrap();
+        let playlist = Arc::downgrade(&self.inner);
+        let clip = loader
+            .user_data(state.clips.len())
+            .on_end(move |index: &mut usize| {
+                if let Some(playlist) = playlist.upgrade() {
+                    playlist.on_end(*index);
+                }
+            })
+            .load()?;
+        state.clips.push(clip);
+        Ok(())
+    }
+
+    /// Returns the number of clips in the playlist.
+    pub fn len(&self) -> usize {
+        self.inner.state.lock().unwrap().clips.len()
+    }
+
+    /// Checks if the playlist has no clips.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the current clip, or `None` if the playlist is empty.
+    pub fn current(&self) -> Option<AudioHandle<usize>> {
+        let state = self.inner.state.lock().unwrap();
+        state.clips.get(state.current).cloned()
+    }
+
+    /// Returns the index of the current clip.
+    pub fn current_index(&self) -> usize {
+        self.inner.state.lock().unwrap().current
+    }
+
+    /// Sets the repeat mode.
+    pub fn set_repeat(&self, repeat: Repeat) {
+        self.inner.state.lock().unwrap().repeat = repeat;
+    }
+
+    /// Gets the repeat mode.
+    pub fn repeat(&self) -> Repeat {
+        self.inner.state.lock().unwrap().repeat
+    }
+
+    /// Plays the current clip.
+    pub fn play(&self) -> Result<(), AudioError> {
+        match self.current() {
+            Some(clip) => clip.play(),
+            None => Ok(()),
+        }
+    }
+
+    /// Pauses the current clip.
+    pub fn stop(&self) -> Result<(), AudioError> {
+        match self.current() {
+            Some(clip) => clip.stop(),
+            None => Ok(()),
+        }
+    }
+
+    /// Skips to the next clip.
+    ///
+    /// The new clip starts from the beginning and only plays if the current one was playing.
+    /// After the last clip this wraps around with [`Repeat::All`] and does nothing otherwise.
+    pub fn next(&self) -> Result<(), AudioError> {
+        self.skip(|current, len, repeat| match repeat {
+            Repeat::All => Some((current + 1) % len),
+            _ if current + 1 < len => Some(current + 1),
+            _ => None,
+        })
+    }
+
+    /// Skips to the previous clip.
+    ///
+    /// Before the first clip this wraps around with [`Repeat::All`] and does nothing otherwise.
+    pub fn previous(&self) -> Result<(), AudioError> {
+        self.skip(|current, len, repeat| match repeat {
+            Repeat::All => Some((current + len - 1) % len),
+            _ if current > 0 => Some(current - 1),
+            _ => None,
+        })
+    }
+
+    fn skip(
+        &self,
+        target: impl FnOnce(usize, usize, Repeat) -> Option<usize>,
+    ) -> Result<(), AudioError> {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.clips.is_empty() {
+            return Ok(());
         }
+        let next = match target(state.current, state.clips.len(), state.repeat) {
+            Some(next) => next,
+            None => return Ok(()),
+        };
+        let current = &state.clips[state.current];
+        let was_playing = current.is_playing();
+        current.reset()?;
+        state.current = next;
+        if was_playing {
+            state.clips[next].play()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Playlist {
+    fn default() -> Self {
+        Playlist::new()
     }
 }
 
-impl<T> Drop for AudioHandle<T> {
+impl Drop for Playlist {
     fn drop(&mut self) {
-        unsafe {
-            removeSound(self.inner.id, &self.inner.context.inner.context);
+        //the clips are released here rather than by an end callback that still holds the playlist
+        let clips = std::mem::take(&mut self.inner.state.lock().unwrap().clips);
+        for clip in &clips {
+            let _ = clip.stop();
+        }
+    }
+}
+
+/// Plays overlapping copies of one sound, like footsteps or hits in a game.
+///
+/// The sound is decoded once and every voice shares the decoded samples. When more than
+/// `max_voices` copies are playing the oldest one is stopped to make room for the new one.
+pub struct SoundPool {
+    audio: Arc<DecodedAudio>,
+    context: Context,
+    max_voices: usize,
+    voices: Mutex<VecDeque<AudioHandle<()>>>,
+}
+
+impl SoundPool {
+    /// Creates a pool that plays `audio` with at most `max_voices` copies at once.
+    ///
+    /// A `max_voices` of zero is treated as one.
+    pub fn new(audio: DecodedAudio, context: Context, max_voices: usize) -> SoundPool {
+        SoundPool {
+            audio: Arc::new(audio),
+            context,
+            max_voices: max_voices.max(1),
+            voices: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Decodes the source of `loader` and creates a pool from it, see [`SoundPool::new`].
+    ///
+    /// Only the source and the context of the loader are used.
+    pub fn from_loader<T, I, P>(
+        loader: AudioLoader<'_, T, I, P>,
+        max_voices: usize,
+    ) -> Result<SoundPool, AudioError>
+    where
+        P: AsRef<Path>,
+        I: void::OnEnd<T>,
+    {
+        let context = loader.context.clone();
+        Ok(SoundPool::new(loader.decode()?, context, max_voices))
+    }
+
+    /// Starts a new copy of the sound and returns its handle.
+    ///
+    /// The handle can be used to change the copy while it plays. Dropping it does not
+    /// stop the copy, which the pool releases after it has ended.
+    pub fn play_one(&self) -> Result<AudioHandle<()>, AudioError> {
+        let mut voices = self.voices.lock().unwrap();
+        voices.retain(|voice| voice.is_playing());
+        while voices.len() >= self.max_voices {
+            if let Some(oldest) = voices.pop_front() {
+                let _ = oldest.stop();
+            }
+        }
+        let voice = AudioLoader::from_decoded(self.audio.clone(), self.context.clone())
+            .autoplay(true)
+            .load()?;
+        voices.push_back(voice.clone());
+        Ok(voice)
+    }
+
+    /// Returns the number of copies that are playing.
+    pub fn voice_count(&self) -> usize {
+        let mut voices = self.voices.lock().unwrap();
+        voices.retain(|voice| voice.is_playing());
+        voices.len()
+    }
+
+    /// Returns the highest number of copies that play at once.
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+
+    /// Stops and releases every copy.
+    pub fn stop(&self) {
+        for voice in self.voices.lock().unwrap().drain(..) {
+            let _ = voice.stop();
         }
     }
 }
@@ -1,21 +1,24 @@
+/// The `on_end` type of a loader that has no `on_end` closure.
+#[derive(Clone, Copy)]
 pub enum Void {}
 
-impl<T> FnOnce<T> for Void {
-    type Output = ();
-
-    extern "rust-call" fn call_once(self, _args: T) {
-        match self {}
-    }
+/// Implemented by the closures accepted by `AudioLoader::on_end` and by [`Void`].
+pub trait OnEnd<T>: Send + 'static {
+    /// Boxes the closure so it can be stored in a handle.
+    fn into_callback(self) -> Box<dyn FnMut(&mut T) + Send>;
 }
 
-impl<T> FnMut<T> for Void {
-    extern "rust-call" fn call_mut(&mut self, _args: T) {
-        match *self {}
+impl<T, F> OnEnd<T> for F
+where
+    F: 'static + FnMut(&mut T) + Send,
+{
+    fn into_callback(self) -> Box<dyn FnMut(&mut T) + Send> {
+        Box::new(self)
     }
 }
 
-impl<T> Fn<T> for Void {
-    extern "rust-call" fn call(&self, _args: T) {
-        match *self {}
+impl<T> OnEnd<T> for Void {
+    fn into_callback(self) -> Box<dyn FnMut(&mut T) + Send> {
+        match self {}
     }
 }
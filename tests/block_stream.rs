@@ -0,0 +1,34 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn stream_yields_played_blocks_until_the_end() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("block_stream.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    let stream = handle.block_stream();
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(200)).unwrap();
+
+    let samples: usize = stream.map(|block| block.len()).sum();
+    assert_eq!(samples, 4800 * 2);
+}
+
+#[test]
+fn slow_consumer_does_not_stall_playback() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("block_stream_slow.wav", 48000 * 10, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    let stream = handle.block_stream();
+    handle.play().unwrap();
+    context.advance(Duration::from_secs(5)).unwrap();
+    assert!(handle.position() >= Duration::from_secs(5));
+
+    // the blocks that did not fit were dropped rather than queued
+    drop(handle);
+    let samples: usize = stream.map(|block| block.len()).sum();
+    assert!(samples > 0 && samples < 48000 * 5 * 2, "{}", samples);
+}
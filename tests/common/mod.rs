@@ -0,0 +1,45 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes a 16 bit wav with a quiet sine of `frames` frames and returns its path.
+///
+/// Files are kept in a directory per test process so parallel test binaries do not collide.
+pub fn write_wav(name: &str, frames: u32, channels: u16, sample_rate: u32) -> PathBuf {
+    let mut data = Vec::with_capacity(frames as usize * channels as usize * 2);
+    for frame in 0..frames {
+        let phase = frame as f32 * 440.0 * 2.0 * std::f32::consts::PI / sample_rate as f32;
+        let sample = (phase.sin() * i16::MAX as f32 * 0.5) as i16;
+        for _ in 0..channels {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+
+    let block_align = channels * 2;
+    let mut bytes = Vec::with_capacity(44 + data.len());
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&data);
+
+    let path = temp_dir().join(name);
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+/// Returns the directory test files of this process are written to.
+pub fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ez_audio_tests_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
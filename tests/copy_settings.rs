@@ -0,0 +1,79 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AttenuationModel, AudioLoader, Context, PlaybackState};
+
+#[test]
+fn copies_pan_tempo_looping_and_loop_region() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("copy_settings.wav", 48000, 2, 48000);
+    let source = AudioLoader::new(&path, context.clone()).load().unwrap();
+    source.set_pan(0.25);
+    source.set_tempo(1.5);
+    source.set_looping(true);
+    source
+        .set_loop_region(Duration::from_millis(100), Duration::from_millis(200))
+        .unwrap();
+
+    let copy = AudioLoader::new(&path, context.clone()).load().unwrap();
+    copy.copy_settings_from(&source);
+    assert_eq!(copy.pan(), 0.25);
+    assert_eq!(copy.tempo(), 1.5);
+    assert!(copy.is_looping());
+
+    // the region keeps the copy inside it once playback passes its end
+    copy.set_tempo(1.0);
+    copy.seek_to(Duration::from_millis(150)).unwrap();
+    copy.play().unwrap();
+    context.advance(Duration::from_millis(300)).unwrap();
+    assert_eq!(copy.state(), PlaybackState::Playing);
+    let position = copy.position();
+    assert!(
+        position >= Duration::from_millis(100) && position < Duration::from_millis(200),
+        "{:?}",
+        position
+    );
+}
+
+#[test]
+fn copies_position_and_attenuation() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("copy_settings_spatial.wav", 48000, 2, 48000);
+    let source = AudioLoader::new(&path, context.clone()).load().unwrap();
+    source.set_position_3d(80.0, 0.0, 0.0);
+    source.set_attenuation_model(AttenuationModel::Linear);
+
+    let plain = AudioLoader::new(&path, context.clone()).load().unwrap();
+    let copy = AudioLoader::new(&path, context.clone()).load().unwrap();
+    copy.copy_settings_from(&source);
+    assert_eq!(copy.attenuation_model(), AttenuationModel::Linear);
+
+    plain.play().unwrap();
+    copy.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert!(copy.post_fader_level() < plain.post_fader_level() * 0.5);
+}
+
+#[test]
+fn copies_the_muted_volume() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("copy_settings_muted.wav", 48000, 2, 48000);
+    let source = AudioLoader::new(&path, context.clone()).load().unwrap();
+    source.set_volume(0.5);
+    source.mute();
+
+    let copy = AudioLoader::new(&path, context.clone()).load().unwrap();
+    copy.copy_settings_from(&source);
+    assert!(copy.is_muted());
+    assert_eq!(copy.volume(), 0.0);
+    copy.unmute();
+    assert_eq!(copy.volume(), 0.5);
+
+    // copying an unmuted clip unmutes the copy
+    copy.mute();
+    source.unmute();
+    copy.copy_settings_from(&source);
+    assert!(!copy.is_muted());
+    assert_eq!(copy.volume(), 0.5);
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{crossfade, AudioError, AudioLoader, Context, CrossfadeCurve, PlaybackState};
+
+#[test]
+fn linear_crossfade_hands_over_to_the_second_clip() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("crossfade_linear.wav", 48000, 2, 48000);
+    let from = AudioLoader::new(&path, context.clone())
+        .looping(true)
+        .load()
+        .unwrap();
+    let to = AudioLoader::new(&path, context.clone())
+        .looping(true)
+        .volume(0.8)
+        .load()
+        .unwrap();
+    from.play().unwrap();
+    crossfade(
+        &from,
+        &to,
+        Duration::from_millis(200),
+        CrossfadeCurve::Linear,
+    )
+    .unwrap();
+    assert!(to.is_playing());
+
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert!((from.volume() - 0.5).abs() < 0.05, "{}", from.volume());
+    assert!((to.volume() - 0.4).abs() < 0.05, "{}", to.volume());
+
+    //the first clip pauses at its original volume once it is silent
+    context.advance(Duration::from_millis(200)).unwrap();
+    assert_eq!(from.state(), PlaybackState::Paused);
+    assert_eq!(from.volume(), 1.0);
+    assert!(to.is_playing());
+    assert_eq!(to.volume(), 0.8);
+}
+
+#[test]
+fn crossfade_requires_one_context() {
+    let path = common::write_wav("crossfade_contexts.wav", 4800, 2, 48000);
+    let from = AudioLoader::new(&path, Context::offline().unwrap())
+        .load()
+        .unwrap();
+    let to = AudioLoader::new(&path, Context::offline().unwrap())
+        .load()
+        .unwrap();
+    assert!(matches!(
+        crossfade(
+            &from,
+            &to,
+            Duration::from_millis(100),
+            CrossfadeCurve::Linear
+        ),
+        Err(AudioError::ContextError)
+    ));
+}
+
+#[test]
+fn constant_power_crossfade_keeps_the_power() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("crossfade_power.wav", 48000, 2, 48000);
+    let from = AudioLoader::new(&path, context.clone())
+        .looping(true)
+        .load()
+        .unwrap();
+    let to = AudioLoader::new(&path, context.clone())
+        .looping(true)
+        .load()
+        .unwrap();
+    from.play().unwrap();
+    crossfade(
+        &from,
+        &to,
+        Duration::from_millis(200),
+        CrossfadeCurve::ConstantPower,
+    )
+    .unwrap();
+
+    context.advance(Duration::from_millis(100)).unwrap();
+    let (out, into) = (from.volume(), to.volume());
+    assert!((out - 0.707).abs() < 0.05, "{}", out);
+    assert!((into - 0.707).abs() < 0.05, "{}", into);
+    assert!((out * out + into * into - 1.0).abs() < 0.05);
+
+    context.advance(Duration::from_millis(200)).unwrap();
+    assert_eq!(from.state(), PlaybackState::Paused);
+    assert_eq!(to.volume(), 1.0);
+}
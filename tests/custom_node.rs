@@ -0,0 +1,33 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn custom_node_processes_every_playing_clip() {
+    let context = Context::offline().unwrap();
+    context.insert_custom_node(|samples, _| samples.iter_mut().for_each(|s| *s = 0.0));
+    let first = AudioLoader::new(
+        common::write_wav("custom_node_a.wav", 48000, 2, 48000),
+        context.clone(),
+    )
+    .load()
+    .unwrap();
+    let second = AudioLoader::new(
+        common::write_wav("custom_node_b.wav", 48000, 2, 48000),
+        context.clone(),
+    )
+    .load()
+    .unwrap();
+    first.play().unwrap();
+    second.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert_eq!(first.post_fader_level(), 0.0);
+    assert_eq!(second.post_fader_level(), 0.0);
+
+    second.set_custom_node_bypass(true);
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert_eq!(first.post_fader_level(), 0.0);
+    assert!(second.post_fader_level() > 0.1);
+}
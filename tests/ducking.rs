@@ -0,0 +1,47 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn music_ducks_under_voice() {
+    let context = Context::offline().unwrap();
+    let music_bus = context.bus("music");
+    let voice_bus = context.bus("voice");
+    music_bus.duck_under(
+        &voice_bus,
+        12.0,
+        Duration::from_millis(20),
+        Duration::from_millis(100),
+    );
+
+    let music_path = common::write_wav("duck_music.wav", 48000 * 2, 2, 48000);
+    let voice_path = common::write_wav("duck_voice.wav", 4800 * 3, 2, 48000);
+    let music = AudioLoader::new(music_path, context.clone())
+        .group(&music_bus)
+        .load()
+        .unwrap();
+    let voice = AudioLoader::new(voice_path, context.clone())
+        .group(&voice_bus)
+        .load()
+        .unwrap();
+
+    music.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    let full = music.peak_level();
+
+    voice.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    let ducked = music.peak_level();
+    assert!((ducked / full - 0.25).abs() < 0.02, "{} {}", full, ducked);
+
+    // the voice has ended and the release is over
+    context.advance(Duration::from_millis(400)).unwrap();
+    assert!((music.peak_level() - full).abs() < 0.01);
+
+    music_bus.clear_duck();
+    voice.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert!((music.peak_level() - full).abs() < 0.01);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AttenuationModel, AudioLoader, Context, FadeCurve, PlaybackState};
+
+#[test]
+fn clear_all_effects_restores_dry_signal() {
+    let context = Context::offline().unwrap();
+    context.insert_custom_node(|samples, _| samples.iter_mut().for_each(|s| *s = 0.0));
+    let path = common::write_wav("effects.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone())
+        .looping(true)
+        .load()
+        .unwrap();
+    handle.set_volume(0.5);
+    handle.set_pan(0.5);
+    handle.set_tempo(2.0);
+    handle.set_custom_node_bypass(true);
+    handle.set_position_3d(10.0, 0.0, 0.0);
+    handle.set_attenuation_model(AttenuationModel::Linear);
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert!(handle.post_fader_level() > 0.0);
+
+    handle.clear_all_effects();
+    assert_eq!(handle.pan(), 0.0);
+    assert_eq!(handle.tempo(), 1.0);
+    assert!(!handle.is_custom_node_bypassed());
+    assert_eq!(handle.attenuation_model(), AttenuationModel::Exponential);
+    assert_eq!(handle.volume(), 0.5);
+    assert!(handle.is_looping());
+
+    //the custom node applies again
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert_eq!(handle.state(), PlaybackState::Playing);
+    assert_eq!(handle.post_fader_level(), 0.0);
+
+    //clearing twice is a no-op
+    handle.clear_all_effects();
+    assert_eq!(handle.volume(), 0.5);
+    assert!(!handle.is_custom_node_bypassed());
+}
+
+#[test]
+fn clear_all_effects_keeps_loop_region() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("effects_region.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone())
+        .looping(true)
+        .load()
+        .unwrap();
+    handle
+        .set_loop_region(Duration::from_millis(100), Duration::from_millis(200))
+        .unwrap();
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(50)).unwrap();
+
+    handle.clear_all_effects();
+    context.advance(Duration::from_millis(400)).unwrap();
+    assert_eq!(handle.state(), PlaybackState::Playing);
+    assert!(handle.position() < Duration::from_millis(200));
+}
+
+#[test]
+fn clear_all_effects_does_nothing_without_effects() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("effects_none.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    let position = handle.position();
+
+    handle.clear_all_effects();
+    assert_eq!(handle.pan(), 0.0);
+    assert_eq!(handle.tempo(), 1.0);
+    assert!(!handle.is_custom_node_bypassed());
+    assert_eq!(handle.attenuation_model(), AttenuationModel::Exponential);
+    assert_eq!(handle.volume(), 1.0);
+    assert_eq!(handle.speed(), 1.0);
+    assert_eq!(handle.state(), PlaybackState::Playing);
+    assert_eq!(handle.position(), position);
+}
+
+#[test]
+fn clear_all_effects_cancels_fades() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("effects_fade.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone())
+        .looping(true)
+        .load()
+        .unwrap();
+    handle.play().unwrap();
+    handle.stop_fade_out(Duration::from_millis(200), FadeCurve::Linear);
+    context.advance(Duration::from_millis(100)).unwrap();
+
+    handle.clear_all_effects();
+    assert_eq!(handle.volume(), 1.0);
+    context.advance(Duration::from_millis(200)).unwrap();
+    assert_eq!(handle.state(), PlaybackState::Playing);
+
+    handle.fade_to(0.0, Duration::from_millis(200), FadeCurve::Linear);
+    context.advance(Duration::from_millis(100)).unwrap();
+    handle.clear_all_effects();
+    let held = handle.volume();
+    assert!(held > 0.4 && held < 0.6);
+    context.advance(Duration::from_millis(200)).unwrap();
+    assert_eq!(handle.volume(), held);
+}
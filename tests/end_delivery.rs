@@ -0,0 +1,41 @@
+mod common;
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn autoplayed_clip_ends_offline() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("autoplay_offline.wav", 1, 2, 48000);
+    let (sender, receiver) = mpsc::channel();
+    let _handle = AudioLoader::new(path, context.clone())
+        .autoplay(true)
+        .on_end(move |_| {
+            sender.send(()).unwrap();
+        })
+        .load()
+        .unwrap();
+    context.advance(Duration::from_millis(10)).unwrap();
+    assert!(receiver.try_recv().is_ok());
+    assert!(receiver.try_recv().is_err());
+}
+
+// the clip can end on the audio thread before load has registered the handle
+#[test]
+fn clip_ending_during_load_is_delivered() {
+    let context = Context::mock().unwrap();
+    for i in 0..20 {
+        let path = common::write_wav(&format!("early_end_{}.wav", i), 1, 2, 48000);
+        let (sender, receiver) = mpsc::channel();
+        let _handle = AudioLoader::new(path, context.clone())
+            .autoplay(true)
+            .on_end(move |_| {
+                let _ = sender.send(());
+            })
+            .load()
+            .unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+}
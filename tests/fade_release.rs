@@ -0,0 +1,60 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn fade_out_releases_clip_when_it_stops() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("fade_release.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.play().unwrap();
+    handle.fade_out_and_release(Duration::from_millis(200));
+
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert_eq!(context.stats().clips, 1);
+    assert_eq!(context.stats().playing, 1);
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(context.stats().clips, 0);
+}
+
+#[test]
+fn fade_out_releases_clip_at_its_end() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("fade_release_end.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.play().unwrap();
+    handle.fade_out_and_release(Duration::from_secs(1));
+
+    context.advance(Duration::from_millis(200)).unwrap();
+    assert_eq!(context.stats().clips, 0);
+}
+
+#[test]
+fn fade_out_of_stopped_clip_releases_right_away() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("fade_release_stopped.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.fade_out_and_release(Duration::from_secs(1));
+    assert_eq!(context.stats().clips, 0);
+}
+
+#[test]
+fn cancelled_fade_out_leaves_clip_to_other_handles() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("fade_release_cancel.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    let other = handle.clone();
+    handle.play().unwrap();
+    handle.fade_out_and_release(Duration::from_millis(200));
+
+    context.advance(Duration::from_millis(100)).unwrap();
+    other.set_volume(1.0);
+    context.advance(Duration::from_millis(200)).unwrap();
+    assert!(other.is_playing());
+    assert_eq!(context.stats().clips, 1);
+
+    drop(other);
+    assert_eq!(context.stats().clips, 0);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use std::fs;
+
+use ez_audio::{AudioError, AudioLoader, Context};
+
+#[test]
+fn garbage_is_unsupported() {
+    let context = Context::offline().unwrap();
+    let path = common::temp_dir().join("garbage.wav");
+    fs::write(&path, vec![0x5a; 4096]).unwrap();
+    match AudioLoader::new(path, context).load() {
+        Err(AudioError::UnsupportedFormat) => {}
+        other => panic!("expected UnsupportedFormat, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn mislabeled_file_is_sniffed() {
+    let context = Context::offline().unwrap();
+    let wav = common::write_wav("mislabeled.wav", 4800, 2, 48000);
+    let path = common::temp_dir().join("mislabeled.foo");
+    fs::rename(wav, &path).unwrap();
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    assert_eq!(handle.sample_rate(), 48000);
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use ez_audio::{AudioLoader, Context};
+
+// ids are global to the process so this is the only test in this binary
+#[test]
+fn ids_are_reused() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("ids.wav", 48, 2, 48000);
+    let kept = AudioLoader::new(&path, context.clone()).load().unwrap();
+
+    let mut max_id = 0;
+    for _ in 0..1000 {
+        let handle = AudioLoader::new(&path, context.clone()).load().unwrap();
+        assert_ne!(handle.id(), kept.id());
+        max_id = max_id.max(handle.id());
+    }
+    assert!(max_id <= kept.id() + 1, "ids grew to {}", max_id);
+    assert_eq!(context.active_sound_count(), 1);
+}
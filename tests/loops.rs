@@ -0,0 +1,44 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn on_loop_counts_wraps() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("loops.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone())
+        .user_data(Vec::new())
+        .looping(true)
+        .load()
+        .unwrap();
+    handle.set_on_loop(|counts: &mut Vec<u64>, count| counts.push(count));
+    handle.play().unwrap();
+
+    context.advance(Duration::from_millis(350)).unwrap();
+    handle.modify_user_data(|counts| assert_eq!(*counts, vec![1, 2, 3]));
+
+    handle.reset().unwrap();
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(150)).unwrap();
+    handle.modify_user_data(|counts| assert_eq!(*counts, vec![1, 2, 3, 1]));
+}
+
+#[test]
+fn on_loop_counts_loop_region_wraps() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("loops_region.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone())
+        .user_data(0u64)
+        .load()
+        .unwrap();
+    handle
+        .set_loop_region(Duration::from_millis(100), Duration::from_millis(200))
+        .unwrap();
+    handle.set_on_loop(|last: &mut u64, count| *last = count);
+    handle.play().unwrap();
+
+    context.advance(Duration::from_millis(450)).unwrap();
+    handle.modify_user_data(|last| assert_eq!(*last, 3));
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::sync::Arc;
+
+use ez_audio::{AudioError, AudioLoader, Context};
+
+#[test]
+fn budget_limits_memory_clips() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("budget.wav", 4800, 2, 48000);
+    let bytes = std::fs::read(&path).unwrap();
+    context.set_memory_budget(bytes.len() * 3 / 2);
+
+    let first = AudioLoader::from_memory(bytes.clone(), context.clone())
+        .load()
+        .unwrap();
+    let stats = context.stats();
+    assert_eq!(stats.clips, 1);
+    assert_eq!(stats.memory, bytes.len());
+    assert_eq!(stats.memory_budget, bytes.len() * 3 / 2);
+
+    let second = AudioLoader::from_memory(bytes.clone(), context.clone()).load();
+    assert!(matches!(second, Err(AudioError::OutOfMemory)));
+
+    // files stream and do not count against the budget
+    let _file = AudioLoader::new(&path, context.clone()).load().unwrap();
+    assert_eq!(context.stats().memory, bytes.len());
+
+    drop(first);
+    assert_eq!(context.stats().memory, 0);
+    AudioLoader::from_memory(bytes, context.clone())
+        .load()
+        .unwrap();
+}
+
+#[test]
+fn shared_decoded_audio_counts_once() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("budget_decoded.wav", 4800, 2, 48000);
+    let audio = Arc::new(AudioLoader::new(path, context.clone()).decode().unwrap());
+    let size = audio.samples.len() * std::mem::size_of::<f32>();
+    context.set_memory_budget(size);
+
+    let first = AudioLoader::from_decoded(audio.clone(), context.clone())
+        .load()
+        .unwrap();
+    let second = AudioLoader::from_decoded(audio, context.clone())
+        .load()
+        .unwrap();
+    first.play().unwrap();
+    let stats = context.stats();
+    assert_eq!(stats.clips, 2);
+    assert_eq!(stats.playing, 1);
+    assert_eq!(stats.memory, size);
+    drop(second);
+}
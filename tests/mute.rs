@@ -0,0 +1,25 @@
+mod common;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn unmute_restores_the_volume_set_while_muted() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("mute.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context).volume(0.5).load().unwrap();
+    handle.mute();
+    assert!(handle.is_muted());
+    assert_eq!(handle.volume(), 0.0);
+
+    handle.set_volume(0.25);
+    assert_eq!(handle.volume(), 0.0);
+    //muting twice keeps the volume to restore
+    handle.mute();
+    handle.unmute();
+    assert!(!handle.is_muted());
+    assert_eq!(handle.volume(), 0.25);
+
+    //unmuting a clip that is not muted does nothing
+    handle.unmute();
+    assert_eq!(handle.volume(), 0.25);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use std::fs;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn long_path_loads() {
+    let context = Context::offline().unwrap();
+    let mut dir = common::temp_dir();
+    for i in 0..8 {
+        dir.push(format!("nested_directory_with_a_long_name_{}", i));
+    }
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("clip_with_a_name_long_enough_to_live_on_the_heap.wav");
+    fs::rename(common::write_wav("long_path.wav", 480, 2, 48000), &path).unwrap();
+
+    // the path is converted for every load so a dangling pointer shows up as a failed or garbled load
+    for _ in 0..32 {
+        let handle = AudioLoader::new(&path, context.clone()).load().unwrap();
+        assert_eq!(handle.path(), path.as_path());
+    }
+}
+
+#[test]
+fn unicode_path_loads() {
+    let context = Context::offline().unwrap();
+    let path = common::temp_dir().join("klänge_音.wav");
+    fs::rename(common::write_wav("unicode_path.wav", 480, 2, 48000), &path).unwrap();
+    AudioLoader::new(&path, context).load().unwrap();
+}
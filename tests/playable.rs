@@ -0,0 +1,33 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context, Playable};
+
+#[test]
+fn handles_with_different_user_data_share_a_collection() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("playable.wav", 48000, 2, 48000);
+    let clips: Vec<Box<dyn Playable>> = vec![
+        Box::new(AudioLoader::new(&path, context.clone()).load().unwrap()),
+        Box::new(
+            AudioLoader::new(&path, context.clone())
+                .user_data(String::from("music"))
+                .load()
+                .unwrap(),
+        ),
+    ];
+    for clip in &clips {
+        clip.set_volume(0.5);
+        clip.play().unwrap();
+    }
+    context.advance(Duration::from_millis(100)).unwrap();
+    for clip in &clips {
+        assert!(clip.is_playing());
+        assert_eq!(clip.volume(), 0.5);
+        assert!(clip.position() >= Duration::from_millis(90));
+        assert_eq!(clip.duration(), Duration::from_secs(1));
+        assert_eq!(clip.path(), path.as_path());
+    }
+    assert_ne!(clips[0].id(), clips[1].id());
+}
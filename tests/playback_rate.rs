@@ -0,0 +1,41 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context, PlaybackState};
+
+#[test]
+fn playback_rate_is_clamped() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("rate_clamp.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.set_playback_rate(u32::MAX);
+    assert_eq!(handle.playback_rate(), 384000);
+    handle.set_playback_rate(1);
+    assert_eq!(handle.playback_rate(), 8000);
+
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+    assert_eq!(handle.state(), PlaybackState::Playing);
+    assert!(handle.position() > Duration::ZERO);
+
+    handle.set_playback_rate(0);
+    assert_eq!(handle.playback_rate(), 48000);
+}
+
+#[test]
+fn speed_is_clamped_to_playback_rates() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("speed_clamp.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    handle.set_speed(1e30);
+    assert_eq!(handle.playback_rate(), 384000);
+    assert_eq!(handle.speed(), 8.0);
+    handle.set_speed(f32::INFINITY);
+    handle.set_speed(f32::NAN);
+    assert_eq!(handle.speed(), 8.0);
+    handle.set_speed(0.5);
+    assert_eq!(handle.playback_rate(), 24000);
+    handle.set_playback_rate(96000);
+    assert_eq!(handle.speed(), 2.0);
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context, PlaybackState, Playlist, Repeat};
+
+fn playlist(context: &Context, name: &str, tracks: usize) -> Playlist {
+    let playlist = Playlist::new();
+    for track in 0..tracks {
+        let path = common::write_wav(&format!("{}_{}.wav", name, track), 4800, 2, 48000);
+        playlist
+            .push(AudioLoader::new(path, context.clone()))
+            .unwrap();
+    }
+    playlist
+}
+
+#[test]
+fn advances_when_a_track_ends() {
+    let context = Context::offline().unwrap();
+    let playlist = playlist(&context, "playlist_advance", 2);
+    playlist.play().unwrap();
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(playlist.current_index(), 1);
+    assert!(playlist.current().unwrap().is_playing());
+
+    //without repeat playback stops after the last track
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(playlist.current_index(), 1);
+    assert_eq!(playlist.current().unwrap().state(), PlaybackState::Stopped);
+}
+
+#[test]
+fn repeat_modes() {
+    let context = Context::offline().unwrap();
+    let playlist = playlist(&context, "playlist_repeat", 2);
+    playlist.set_repeat(Repeat::One);
+    playlist.play().unwrap();
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(playlist.current_index(), 0);
+    assert!(playlist.current().unwrap().is_playing());
+
+    playlist.set_repeat(Repeat::All);
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(playlist.current_index(), 1);
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(playlist.current_index(), 0);
+    assert!(playlist.current().unwrap().is_playing());
+}
+
+#[test]
+fn next_and_previous() {
+    let context = Context::offline().unwrap();
+    let playlist = playlist(&context, "playlist_skip", 3);
+
+    //skipping a track that is not playing does not start the next one
+    playlist.next().unwrap();
+    assert_eq!(playlist.current_index(), 1);
+    assert!(!playlist.current().unwrap().is_playing());
+
+    playlist.play().unwrap();
+    playlist.next().unwrap();
+    assert_eq!(playlist.current_index(), 2);
+    assert!(playlist.current().unwrap().is_playing());
+
+    //the ends of the playlist only wrap with Repeat::All
+    playlist.next().unwrap();
+    assert_eq!(playlist.current_index(), 2);
+    playlist.set_repeat(Repeat::All);
+    playlist.next().unwrap();
+    assert_eq!(playlist.current_index(), 0);
+    playlist.previous().unwrap();
+    assert_eq!(playlist.current_index(), 2);
+    playlist.set_repeat(Repeat::Off);
+    playlist.previous().unwrap();
+    playlist.previous().unwrap();
+    playlist.previous().unwrap();
+    assert_eq!(playlist.current_index(), 0);
+    assert!(playlist.current().unwrap().is_playing());
+}
+
+#[test]
+fn empty_playlist_does_nothing() {
+    let playlist = Playlist::new();
+    assert!(playlist.is_empty());
+    assert!(playlist.current().is_none());
+    playlist.play().unwrap();
+    playlist.next().unwrap();
+    playlist.previous().unwrap();
+    assert_eq!(playlist.current_index(), 0);
+}
+
+#[test]
+fn dropping_stops_the_clips() {
+    let context = Context::offline().unwrap();
+    let playlist = playlist(&context, "playlist_drop", 2);
+    playlist.play().unwrap();
+    let current = playlist.current().unwrap();
+    drop(playlist);
+    assert!(!current.is_playing());
+
+    //ending the clip no longer reaches the playlist
+    current.play().unwrap();
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(current.state(), PlaybackState::Stopped);
+}
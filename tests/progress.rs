@@ -0,0 +1,41 @@
+mod common;
+
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn reports_progress_while_playing() {
+    let context = Context::mock().unwrap();
+    let path = common::write_wav("progress.wav", 14400, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    let (sender, receiver) = channel();
+    handle.set_on_progress(Duration::from_millis(50), move |position| {
+        let _ = sender.send(position);
+    });
+    handle.play().unwrap();
+    handle.wait();
+
+    let positions: Vec<Duration> = receiver.try_iter().collect();
+    assert!(positions.len() >= 2, "{:?}", positions);
+    assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(positions
+        .iter()
+        .all(|position| *position <= handle.duration()));
+}
+
+#[test]
+fn cleared_progress_is_not_reported() {
+    let context = Context::mock().unwrap();
+    let path = common::write_wav("progress_cleared.wav", 9600, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    let (sender, receiver) = channel();
+    handle.set_on_progress(Duration::from_millis(20), move |position| {
+        let _ = sender.send(position);
+    });
+    handle.clear_on_progress();
+    handle.play().unwrap();
+    handle.wait();
+    assert!(receiver.try_recv().is_err());
+}
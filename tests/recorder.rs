@@ -0,0 +1,25 @@
+use std::thread;
+use std::time::Duration;
+
+use ez_audio::{input_devices, Context, Recorder};
+
+#[test]
+fn records_from_the_null_backend() {
+    let context = Context::mock().unwrap();
+    let device = input_devices(context.clone()).next().unwrap();
+    let recorder = Recorder::with_device(&device).unwrap();
+    assert!(!recorder.is_recording());
+    assert!(recorder.channels() > 0);
+    assert!(recorder.sample_rate() > 0);
+
+    recorder.start().unwrap();
+    assert!(recorder.is_recording());
+    thread::sleep(Duration::from_millis(100));
+    recorder.stop();
+    assert!(!recorder.is_recording());
+
+    let samples = recorder.take();
+    assert!(!samples.is_empty());
+    assert_eq!(samples.len() % recorder.channels() as usize, 0);
+    assert!(recorder.take().is_empty());
+}
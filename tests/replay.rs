@@ -0,0 +1,52 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ez_audio::{AudioHandle, AudioLoader, Context, PlaybackState};
+
+fn load_counted(context: &Context, name: &str) -> (AudioHandle<()>, Arc<AtomicUsize>) {
+    let path = common::write_wav(name, 480, 2, 48000);
+    let ends = Arc::new(AtomicUsize::new(0));
+    let counter = ends.clone();
+    let handle = AudioLoader::new(path, context.clone())
+        .on_end(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .load()
+        .unwrap();
+    (handle, ends)
+}
+
+#[test]
+fn play_after_end_restarts() {
+    let context = Context::offline().unwrap();
+    let (handle, ends) = load_counted(&context, "restart.wav");
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(50)).unwrap();
+    assert_eq!(handle.state(), PlaybackState::Stopped);
+
+    handle.play().unwrap();
+    assert_eq!(handle.state(), PlaybackState::Playing);
+    context.advance(Duration::from_millis(50)).unwrap();
+    assert_eq!(ends.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn play_after_end_is_ignored_without_replay() {
+    let context = Context::offline().unwrap();
+    let (handle, ends) = load_counted(&context, "ignored.wav");
+    handle.set_replay_on_play(false);
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(50)).unwrap();
+
+    handle.play().unwrap();
+    assert_eq!(handle.state(), PlaybackState::Stopped);
+    context.advance(Duration::from_millis(50)).unwrap();
+    assert_eq!(ends.load(Ordering::SeqCst), 1);
+
+    handle.reset().unwrap();
+    handle.play().unwrap();
+    assert_eq!(handle.state(), PlaybackState::Playing);
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use ez_audio::{AudioLoader, Backend, ContextBuilder};
+
+#[test]
+fn context_rate_conversion_is_resampling() {
+    let context = ContextBuilder::new()
+        .backend(Backend::Null)
+        .sample_rate(22050)
+        .build()
+        .unwrap();
+    let path = common::write_wav("context_rate.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(&path, context.clone()).load().unwrap();
+    assert_eq!(handle.sample_rate(), 48000);
+    assert_eq!(handle.playback_rate(), 22050);
+    assert!(handle.is_resampling());
+}
+
+#[test]
+fn match_device_rate_keeps_file_rate() {
+    let context = ContextBuilder::new()
+        .backend(Backend::Null)
+        .sample_rate(22050)
+        .build()
+        .unwrap();
+    let path = common::write_wav("context_rate_match.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(&path, context)
+        .match_device_rate(true)
+        .load()
+        .unwrap();
+    assert_eq!(handle.playback_rate(), 48000);
+    assert!(!handle.is_resampling());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context, PlaybackState};
+
+#[test]
+fn empty_clip_has_zero_duration() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("empty.wav", 0, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    assert_eq!(handle.duration(), Duration::from_secs(0));
+    assert_eq!(handle.position(), Duration::from_secs(0));
+    handle.seek_to(Duration::from_millis(10)).unwrap();
+    assert_eq!(handle.position(), Duration::from_secs(0));
+}
+
+#[test]
+fn empty_clip_ends_once() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("empty_end.wav", 0, 2, 48000);
+    let ends = Arc::new(AtomicUsize::new(0));
+    let counter = ends.clone();
+    let handle = AudioLoader::new(path, context.clone())
+        .on_end(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .load()
+        .unwrap();
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(50)).unwrap();
+    context.advance(Duration::from_millis(50)).unwrap();
+    assert_eq!(ends.load(Ordering::SeqCst), 1);
+    assert_eq!(handle.state(), PlaybackState::Stopped);
+}
+
+#[test]
+fn tiny_clip_plays_to_the_end() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("tiny.wav", 5, 1, 48000);
+    let ends = Arc::new(AtomicUsize::new(0));
+    let counter = ends.clone();
+    let handle = AudioLoader::new(path, context.clone())
+        .on_end(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .load()
+        .unwrap();
+    assert_eq!(handle.duration(), Duration::from_secs(0));
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(20)).unwrap();
+    assert_eq!(ends.load(Ordering::SeqCst), 1);
+    assert_eq!(handle.state(), PlaybackState::Stopped);
+}
+
+#[test]
+fn stop_races_the_end_of_a_short_clip() {
+    let context = Context::mock().unwrap();
+    let path = common::write_wav("stop_race.wav", 48, 2, 48000);
+    for _ in 0..20 {
+        let handle = AudioLoader::new(&path, context.clone()).load().unwrap();
+        handle.play().unwrap();
+        handle.stop().unwrap();
+        let handle = AudioLoader::new(&path, context.clone())
+            .autoplay(true)
+            .load()
+            .unwrap();
+        handle.stop().unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context, FadeCurve, PlaybackState};
+
+#[test]
+fn snapshot_matches_handle() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("snapshot.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.set_pan(-0.5);
+    handle.set_speed(2.0);
+    handle.set_looping(true);
+    handle.play().unwrap();
+    context.advance(Duration::from_millis(100)).unwrap();
+
+    let snapshot = handle.snapshot();
+    assert_eq!(snapshot.state, PlaybackState::Playing);
+    assert_eq!(snapshot.pan, handle.pan());
+    assert_eq!(snapshot.speed, handle.speed());
+    assert!(snapshot.looping);
+    assert_eq!(snapshot.position, handle.position());
+    assert_eq!(snapshot.duration, Duration::from_secs(1));
+}
+
+#[test]
+fn snapshot_follows_fades() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("snapshot_fade.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone()).load().unwrap();
+    handle.play().unwrap();
+    handle.fade_to(0.0, Duration::from_millis(200), FadeCurve::Linear);
+    context.advance(Duration::from_millis(100)).unwrap();
+
+    let snapshot = handle.snapshot();
+    assert!(
+        snapshot.volume > 0.0 && snapshot.volume < 1.0,
+        "{}",
+        snapshot.volume
+    );
+    assert_eq!(snapshot.volume, handle.volume());
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::time::Duration;
+
+use ez_audio::{AudioLoader, Context, PlaybackState, SoundPool};
+
+#[test]
+fn steals_the_oldest_voice_at_max_voices() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("sound_pool.wav", 48000, 2, 48000);
+    let pool = SoundPool::from_loader(AudioLoader::new(path, context.clone()), 2).unwrap();
+
+    let first = pool.play_one().unwrap();
+    let second = pool.play_one().unwrap();
+    assert_eq!(pool.voice_count(), 2);
+    context.advance(Duration::from_millis(50)).unwrap();
+
+    let third = pool.play_one().unwrap();
+    assert_eq!(pool.voice_count(), 2);
+    assert!(!first.is_playing());
+    assert!(second.is_playing());
+    assert!(third.is_playing());
+}
+
+#[test]
+fn finished_voices_make_room() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("sound_pool_short.wav", 4800, 2, 48000);
+    let pool = SoundPool::from_loader(AudioLoader::new(path, context.clone()), 2).unwrap();
+
+    let first = pool.play_one().unwrap();
+    context.advance(Duration::from_millis(150)).unwrap();
+    assert_eq!(first.state(), PlaybackState::Stopped);
+    assert_eq!(pool.voice_count(), 0);
+
+    let second = pool.play_one().unwrap();
+    let third = pool.play_one().unwrap();
+    assert!(second.is_playing() && third.is_playing());
+}
+
+#[test]
+fn zero_voices_is_one() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("sound_pool_zero.wav", 48000, 2, 48000);
+    let pool = SoundPool::from_loader(AudioLoader::new(path, context), 0).unwrap();
+    let first = pool.play_one().unwrap();
+    let second = pool.play_one().unwrap();
+    assert!(!first.is_playing());
+    assert!(second.is_playing());
+    assert_eq!(pool.voice_count(), 1);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use ez_audio::{AudioLoader, Context, MAX_VOLUME};
+
+#[test]
+fn loader_volume_is_applied_at_load() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("volume_loader.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context.clone())
+        .volume(0.5)
+        .load()
+        .unwrap();
+    assert_eq!(handle.volume(), 0.5);
+}
+
+#[test]
+fn loader_volume_is_clamped() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("volume_clamped.wav", 4800, 2, 48000);
+    let loud = AudioLoader::new(&path, context.clone())
+        .volume(MAX_VOLUME * 2.0)
+        .load()
+        .unwrap();
+    assert_eq!(loud.volume(), MAX_VOLUME);
+    let negative = AudioLoader::new(&path, context.clone())
+        .volume(-1.0)
+        .load()
+        .unwrap();
+    assert_eq!(negative.volume(), 0.0);
+    let nan = AudioLoader::new(&path, context.clone())
+        .volume(f32::NAN)
+        .load()
+        .unwrap();
+    assert_eq!(nan.volume(), 0.0);
+}
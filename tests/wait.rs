@@ -0,0 +1,72 @@
+mod common;
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use ez_audio::{AudioLoader, Context, PlaybackState};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = TaskContext::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn wait_returns_after_the_end() {
+    let context = Context::mock().unwrap();
+    let path = common::write_wav("wait_end.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    handle.play().unwrap();
+    let start = Instant::now();
+    handle.wait();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(handle.state(), PlaybackState::Stopped);
+
+    //a clip that is not playing does not block
+    handle.wait();
+}
+
+#[test]
+fn wait_returns_when_paused() {
+    let context = Context::mock().unwrap();
+    let path = common::write_wav("wait_paused.wav", 48000, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    handle.play().unwrap();
+    let stopper = handle.clone();
+    let pause = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        stopper.stop().unwrap();
+    });
+    handle.wait();
+    pause.join().unwrap();
+    assert_eq!(handle.state(), PlaybackState::Paused);
+}
+
+#[test]
+fn finished_resolves_after_the_end() {
+    let context = Context::mock().unwrap();
+    let path = common::write_wav("wait_finished.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    handle.play().unwrap();
+    block_on(handle.finished());
+    assert_eq!(handle.state(), PlaybackState::Stopped);
+    block_on(handle.finished());
+}
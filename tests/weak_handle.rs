@@ -0,0 +1,20 @@
+mod common;
+
+use ez_audio::{AudioLoader, Context};
+
+#[test]
+fn upgrade_fails_once_the_last_handle_is_dropped() {
+    let context = Context::offline().unwrap();
+    let path = common::write_wav("weak_handle.wav", 4800, 2, 48000);
+    let handle = AudioLoader::new(path, context).load().unwrap();
+    let weak = handle.downgrade();
+
+    let upgraded = weak.upgrade().unwrap();
+    assert_eq!(upgraded.id(), handle.id());
+    drop(handle);
+    //an upgraded handle keeps the clip alive too
+    assert!(weak.upgrade().is_some());
+    drop(upgraded);
+    assert!(weak.upgrade().is_none());
+    assert!(weak.clone().upgrade().is_none());
+}